@@ -0,0 +1,198 @@
+//! Frame-by-frame animation of a [`SpriteSheet`].
+
+use crate::sprite::{BlitFlags, SpriteSheet};
+
+/// How an animation continues after its last frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+  /// Starts again from the first frame.
+  Loop,
+  /// Plays backwards to the first frame, then forwards again.
+  PingPong,
+  /// Stays on the last frame.
+  Once,
+}
+
+/// A single frame of an animation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Frame {
+  /// The index of the frame in the sprite sheet.
+  pub index: u32,
+  /// The number of updates the frame is shown for.
+  pub duration: u16,
+}
+
+impl Frame {
+  /// Create a new frame.
+  #[inline]
+  pub const fn new(index: u32, duration: u16) -> Self {
+    Self { index, duration }
+  }
+}
+
+/// Plays through a list of frames, advancing once per update.
+///
+/// ```ignore
+/// const WALK: [Frame; 3] =
+///   [Frame::new(0, 8), Frame::new(1, 8), Frame::new(2, 8)];
+///
+/// let mut walk = Animation::new(&WALK, Mode::PingPong);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Animation<'a> {
+  frames: &'a [Frame],
+  mode: Mode,
+  current: usize,
+  elapsed: u16,
+  backwards: bool,
+  finished: bool,
+}
+
+impl<'a> Animation<'a> {
+  /// Create a new animation starting on the first frame.
+  ///
+  /// Panics if there are no frames.
+  #[inline]
+  pub const fn new(frames: &'a [Frame], mode: Mode) -> Self {
+    assert!(!frames.is_empty(), "animation has no frames");
+
+    Self {
+      frames,
+      mode,
+      current: 0,
+      elapsed: 0,
+      backwards: false,
+      finished: false,
+    }
+  }
+
+  /// Goes back to the first frame.
+  #[inline]
+  pub fn reset(&mut self) {
+    self.current = 0;
+    self.elapsed = 0;
+    self.backwards = false;
+    self.finished = false;
+  }
+
+  /// Advances the animation by an update, which should happen once per
+  /// frame.
+  pub fn update(&mut self) {
+    if self.finished {
+      return;
+    }
+
+    self.elapsed += 1;
+    if self.elapsed < self.frames[self.current].duration {
+      return;
+    }
+    self.elapsed = 0;
+
+    let last = self.frames.len() - 1;
+    match self.mode {
+      Mode::Loop => self.current = (self.current + 1) % self.frames.len(),
+      Mode::Once if self.current == last => self.finished = true,
+      Mode::Once => self.current += 1,
+      Mode::PingPong if last == 0 => {}
+      Mode::PingPong => {
+        if self.current == last {
+          self.backwards = true;
+        } else if self.current == 0 {
+          self.backwards = false;
+        }
+
+        match self.backwards {
+          true => self.current -= 1,
+          false => self.current += 1,
+        }
+      }
+    }
+  }
+
+  /// The sprite sheet index of the current frame.
+  #[inline]
+  pub fn frame(&self) -> u32 {
+    self.frames[self.current].index
+  }
+
+  /// Whether an animation played [`Mode::Once`] has shown its last frame
+  /// for its full duration.
+  #[inline]
+  pub fn is_finished(&self) -> bool {
+    self.finished
+  }
+
+  /// Draws the current frame with its top-left corner at `x` and `y`.
+  #[inline]
+  pub fn draw(&self, sheet: &SpriteSheet, x: i32, y: i32, flags: BlitFlags) {
+    sheet.draw(self.frame(), x, y, flags);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const FRAMES: [Frame; 3] =
+    [Frame::new(10, 2), Frame::new(11, 1), Frame::new(12, 3)];
+
+  /// Returns the frame shown before each of the next `N` updates.
+  fn play<const N: usize>(animation: &mut Animation) -> [u32; N] {
+    core::array::from_fn(|_| {
+      let frame = animation.frame();
+      animation.update();
+      frame
+    })
+  }
+
+  #[test]
+  fn loops() {
+    let mut animation = Animation::new(&FRAMES, Mode::Loop);
+
+    assert_eq!(
+      play(&mut animation),
+      [10, 10, 11, 12, 12, 12, 10, 10, 11, 12, 12, 12, 10]
+    );
+    assert!(!animation.is_finished());
+  }
+
+  #[test]
+  fn ping_pongs() {
+    let mut animation = Animation::new(&FRAMES, Mode::PingPong);
+
+    assert_eq!(
+      play(&mut animation),
+      [10, 10, 11, 12, 12, 12, 11, 10, 10, 11, 12, 12, 12, 11]
+    );
+    assert!(!animation.is_finished());
+  }
+
+  #[test]
+  fn ping_pongs_single_frame() {
+    let mut animation = Animation::new(&FRAMES[..1], Mode::PingPong);
+
+    assert_eq!(play(&mut animation), [10; 5]);
+  }
+
+  #[test]
+  fn plays_once() {
+    let mut animation = Animation::new(&FRAMES, Mode::Once);
+
+    assert_eq!(play(&mut animation), [10, 10, 11, 12, 12]);
+    assert!(!animation.is_finished());
+    animation.update();
+    assert!(animation.is_finished());
+    assert_eq!(play(&mut animation), [12; 4]);
+    assert!(animation.is_finished());
+
+    animation.reset();
+    assert!(!animation.is_finished());
+    assert_eq!(play(&mut animation), [10, 10, 11]);
+  }
+
+  #[test]
+  #[should_panic = "animation has no frames"]
+  fn new_without_frames() {
+    Animation::new(&[], Mode::Loop);
+  }
+}
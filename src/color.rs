@@ -0,0 +1,383 @@
+//! Conversions and blending for [`Color`].
+
+use crate::{Color, Palette, Palettes};
+
+/// A colour as hue, saturation and value.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Hsv {
+  /// The hue, in degrees from 0 up to 360.
+  pub h: f32,
+  /// The saturation, from 0 to 1.
+  pub s: f32,
+  /// The value, from 0 to 1.
+  pub v: f32,
+}
+
+/// A colour as hue, saturation and lightness.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Hsl {
+  /// The hue, in degrees from 0 up to 360.
+  pub h: f32,
+  /// The saturation, from 0 to 1.
+  pub s: f32,
+  /// The lightness, from 0 to 1.
+  pub l: f32,
+}
+
+impl Color {
+  /// Parses a colour from hex digits like `"#e0f8cf"`, or [`None`] if it
+  /// is invalid.
+  ///
+  /// The leading `#` is optional, and three digit shorthands like `"#fc0"`
+  /// are accepted too.
+  pub const fn from_hex(hex: &str) -> Option<Self> {
+    const fn digit(byte: u8) -> Option<u8> {
+      match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+      }
+    }
+
+    let digits = match hex.as_bytes() {
+      [b'#', digits @ ..] => digits,
+      digits => digits,
+    };
+
+    let mut values = [0; 6];
+    let mut i = 0;
+    while i < digits.len() && i < values.len() {
+      values[i] = match digit(digits[i]) {
+        Some(value) => value,
+        None => return None,
+      };
+      i += 1;
+    }
+
+    match digits.len() {
+      3 => Some(Self::rgb(
+        values[0] * 0x11,
+        values[1] * 0x11,
+        values[2] * 0x11,
+      )),
+      6 => Some(Self::rgb(
+        values[0] << 4 | values[1],
+        values[2] << 4 | values[3],
+        values[4] << 4 | values[5],
+      )),
+      _ => None,
+    }
+  }
+
+  /// Converts this colour to hue, saturation and value.
+  pub fn to_hsv(self) -> Hsv {
+    let (h, max, min) = self.hue();
+    let s = if max == 0.0 { 0.0 } else { (max - min) / max };
+
+    Hsv { h, s, v: max }
+  }
+
+  /// Converts hue, saturation and value to a colour.
+  pub fn from_hsv(hsv: Hsv) -> Self {
+    let (s, v) = (clamp01(hsv.s), clamp01(hsv.v));
+    let chroma = v * s;
+
+    Self::from_hue(hsv.h, chroma, v - chroma)
+  }
+
+  /// Converts this colour to hue, saturation and lightness.
+  pub fn to_hsl(self) -> Hsl {
+    let (h, max, min) = self.hue();
+    let l = (max + min) / 2.0;
+    let s = match max == min {
+      true => 0.0,
+      false => (max - min) / (1.0 - (2.0 * l - 1.0).abs()),
+    };
+
+    Hsl { h, s, l }
+  }
+
+  /// Converts hue, saturation and lightness to a colour.
+  pub fn from_hsl(hsl: Hsl) -> Self {
+    let (s, l) = (clamp01(hsl.s), clamp01(hsl.l));
+    let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+
+    Self::from_hue(hsl.h, chroma, l - chroma / 2.0)
+  }
+
+  /// Returns the hue along with the largest and smallest channels.
+  fn hue(self) -> (f32, f32, f32) {
+    let [r, g, b] = [self.r, self.g, self.b].map(|c| c as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let h = if delta == 0.0 {
+      0.0
+    } else if max == r {
+      60.0 * ((g - b) / delta)
+    } else if max == g {
+      60.0 * ((b - r) / delta + 2.0)
+    } else {
+      60.0 * ((r - g) / delta + 4.0)
+    };
+
+    (wrap(h, 360.0), max, min)
+  }
+
+  /// Builds a colour from a hue, its chroma and the amount added to every
+  /// channel.
+  fn from_hue(h: f32, chroma: f32, m: f32) -> Self {
+    let h = wrap(h, 360.0) / 60.0;
+    let x = chroma * (1.0 - (wrap(h, 2.0) - 1.0).abs());
+
+    let (r, g, b) = match h as u8 {
+      0 => (chroma, x, 0.0),
+      1 => (x, chroma, 0.0),
+      2 => (0.0, chroma, x),
+      3 => (0.0, x, chroma),
+      4 => (x, 0.0, chroma),
+      _ => (chroma, 0.0, x),
+    };
+
+    Self::rgb(channel(r + m), channel(g + m), channel(b + m))
+  }
+
+  /// Blends towards `other`, with `t` from 0 for this colour up to 255 for
+  /// `other`.
+  pub const fn lerp(self, other: Self, t: u8) -> Self {
+    const fn mix(a: u8, b: u8, t: u8) -> u8 {
+      let (a, b, t) = (a as u32, b as u32, t as u32);
+      ((a * (255 - t) + b * t + 127) / 255) as u8
+    }
+
+    Self::rgb(
+      mix(self.r, other.r, t),
+      mix(self.g, other.g, t),
+      mix(self.b, other.b, t),
+    )
+  }
+
+  /// Blends towards `other` in linear light, with `t` from 0 for this colour
+  /// up to 255 for `other`.
+  ///
+  /// This avoids the dark midpoints of [`Color::lerp`], approximating the
+  /// sRGB curve with a gamma of 2.
+  pub const fn lerp_gamma(self, other: Self, t: u8) -> Self {
+    const fn mix(a: u8, b: u8, t: u8) -> u8 {
+      let (a, b, t) = ((a as u32).pow(2), (b as u32).pow(2), t as u32);
+      ((a * (255 - t) + b * t + 127) / 255).isqrt() as u8
+    }
+
+    Self::rgb(
+      mix(self.r, other.r, t),
+      mix(self.g, other.g, t),
+      mix(self.b, other.b, t),
+    )
+  }
+
+  /// Returns how bright this colour appears, from 0 to 255.
+  ///
+  /// Uses the Rec. 709 weights for each channel.
+  pub const fn luminance(self) -> u8 {
+    let sum = 2126 * self.r as u32 + 7152 * self.g as u32 + 722 * self.b as u32;
+    ((sum + 5000) / 10000) as u8
+  }
+}
+
+impl Palettes {
+  /// Returns the palette index whose colour is closest to `color`.
+  ///
+  /// Ties go to the lowest index.
+  pub const fn nearest(self, color: Color) -> Palette {
+    const fn distance(a: Color, b: Color) -> u32 {
+      let dr = a.r.abs_diff(b.r) as u32;
+      let dg = a.g.abs_diff(b.g) as u32;
+      let db = a.b.abs_diff(b.b) as u32;
+      // Weighted towards green, which the eye is most sensitive to.
+      2 * dr * dr + 4 * dg * dg + 3 * db * db
+    }
+
+    let colors = self.colors();
+    let mut nearest = 0;
+    let mut i = 1;
+    while i < colors.len() {
+      if distance(colors[i], color) < distance(colors[nearest], color) {
+        nearest = i;
+      }
+      i += 1;
+    }
+
+    Palette::from_index(nearest as u8)
+  }
+}
+
+/// Returns the index of the current palette colour closest to `color`.
+///
+/// [WASM-4 Docs](https://wasm4.org/docs/reference/memory/#palette)
+#[inline]
+pub fn nearest_palette_index(color: Color) -> Palette {
+  Palettes::load_all().nearest(color)
+}
+
+/// Wraps `value` into the range from 0 up to `max`.
+#[inline]
+fn wrap(value: f32, max: f32) -> f32 {
+  let value = value - max * ((value / max) as i32 as f32);
+  if value < 0.0 {
+    value + max
+  } else {
+    value
+  }
+}
+
+#[inline]
+fn clamp01(value: f32) -> f32 {
+  value.clamp(0.0, 1.0)
+}
+
+/// Converts a channel from 0 to 1 into a byte, rounding to the nearest.
+#[inline]
+fn channel(value: f32) -> u8 {
+  (clamp01(value) * 255.0 + 0.5) as u8
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const BLACK: Color = Color::rgb(0, 0, 0);
+  const WHITE: Color = Color::rgb(255, 255, 255);
+
+  /// Every 17th value of each channel, including both ends.
+  fn samples() -> impl Iterator<Item = Color> {
+    let steps = || (0..=255).step_by(17);
+    steps().flat_map(move |r| {
+      steps().flat_map(move |g| steps().map(move |b| Color::rgb(r, g, b)))
+    })
+  }
+
+  #[test]
+  fn from_hex() {
+    let hex = |hex| Color::from_hex(hex).map(Color::to_u32);
+
+    assert_eq!(hex("#e0f8cf"), Some(0xe0f8cf));
+    assert_eq!(hex("E0F8CF"), Some(0xe0f8cf));
+    assert_eq!(hex("#fc0"), Some(0xffcc00));
+    assert_eq!(hex("Fc0"), Some(0xffcc00));
+    assert_eq!(hex("#000000"), Some(0));
+
+    for invalid in [
+      "", "#", "#e0f8cg", "#fcg", "#12345", "#1234", "#1234567", "##fc0",
+      "fc0 ", "+fc0", "ééé",
+    ] {
+      assert_eq!(hex(invalid), None, "{invalid:?}");
+    }
+  }
+
+  #[test]
+  fn hsv_round_trip() {
+    for color in samples() {
+      let hsv = color.to_hsv();
+      assert_eq!(Color::from_hsv(hsv).to_u32(), color.to_u32(), "{hsv:?}");
+    }
+  }
+
+  #[test]
+  fn hsv_primaries() {
+    let hsv = Color::rgb(0, 255, 0).to_hsv();
+    assert_eq!((hsv.h, hsv.s, hsv.v), (120.0, 1.0, 1.0));
+
+    let blue = Hsv {
+      h: 240.0 - 720.0,
+      s: 2.0,
+      v: 1.0,
+    };
+    assert_eq!(Color::from_hsv(blue).to_u32(), 0x0000ff);
+  }
+
+  #[test]
+  fn hsv_grey() {
+    let hsv = Color::rgb(0x80, 0x80, 0x80).to_hsv();
+    assert_eq!((hsv.h, hsv.s), (0.0, 0.0));
+    assert!((hsv.v - 0x80 as f32 / 255.0).abs() < 1e-6);
+
+    assert_eq!(BLACK.to_hsv().s, 0.0);
+  }
+
+  #[test]
+  fn hsl_round_trip() {
+    for color in samples() {
+      let hsl = color.to_hsl();
+      assert_eq!(Color::from_hsl(hsl).to_u32(), color.to_u32(), "{hsl:?}");
+    }
+  }
+
+  #[test]
+  fn hsl_grey() {
+    for grey in [BLACK, Color::rgb(0x55, 0x55, 0x55), WHITE] {
+      let hsl = grey.to_hsl();
+      assert_eq!((hsl.h, hsl.s), (0.0, 0.0));
+      assert_eq!(Color::from_hsl(hsl).to_u32(), grey.to_u32());
+    }
+
+    let red = Hsl {
+      h: 0.0,
+      s: 1.0,
+      l: 0.5,
+    };
+    assert_eq!(Color::from_hsl(red).to_u32(), 0xff0000);
+  }
+
+  #[test]
+  fn lerp_ends() {
+    let lerp = |a: Color, b: Color, t| a.lerp(b, t).to_u32();
+
+    assert_eq!(lerp(BLACK, WHITE, 0), 0x000000);
+    assert_eq!(lerp(BLACK, WHITE, 255), 0xffffff);
+    assert_eq!(lerp(WHITE, BLACK, 0), 0xffffff);
+    assert_eq!(lerp(WHITE, BLACK, 255), 0x000000);
+
+    let (a, b) = (Color::rgb(12, 200, 99), Color::rgb(250, 3, 99));
+    assert_eq!(lerp(a, b, 255), b.to_u32());
+    assert_eq!(lerp(b, a, 255), a.to_u32());
+  }
+
+  #[test]
+  fn lerp_midpoint() {
+    assert_eq!(BLACK.lerp(WHITE, 128).to_u32(), 0x808080);
+    assert_eq!(WHITE.lerp(BLACK, 128).to_u32(), 0x7f7f7f);
+  }
+
+  #[test]
+  fn lerp_gamma_ends() {
+    assert_eq!(BLACK.lerp_gamma(WHITE, 255).to_u32(), 0xffffff);
+    assert_eq!(WHITE.lerp_gamma(BLACK, 255).to_u32(), 0x000000);
+  }
+
+  #[test]
+  fn luminance() {
+    assert_eq!(BLACK.luminance(), 0);
+    assert_eq!(WHITE.luminance(), 255);
+    assert_eq!(Color::rgb(255, 0, 0).luminance(), 54);
+    assert_eq!(Color::rgb(0, 255, 0).luminance(), 182);
+    assert_eq!(Color::rgb(0, 0, 255).luminance(), 18);
+    assert_eq!(Color::rgb(0x80, 0x80, 0x80).luminance(), 0x80);
+  }
+
+  #[test]
+  fn nearest() {
+    let palettes = Palettes::from_u32([0xe0f8cf, 0x86c06c, 0x306850, 0x071821]);
+    let nearest = |color| palettes.nearest(Color::from_u32(color)) as u8;
+
+    assert_eq!(nearest(0xe0f8cf), 0);
+    assert_eq!(nearest(0x306850), 2);
+    assert_eq!(nearest(0xffffff), 0);
+    assert_eq!(nearest(0x000000), 3);
+    assert_eq!(nearest(0x80c070), 1);
+
+    let ties = Palettes::from_u32([0x000000, 0x202020, 0x202020, 0xffffff]);
+    assert_eq!(ties.nearest(Color::from_u32(0x202020)) as u8, 1);
+  }
+}
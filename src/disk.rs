@@ -0,0 +1,416 @@
+//! Persistent storage that survives between sessions.
+//!
+//! Values implementing [`Save`] are stored behind a small header holding their
+//! version and length, so a newer build of a game can still load, or migrate,
+//! the saves of an older one.
+//!
+//! [WASM-4 Docs](https://wasm4.org/docs/guides/diskw)
+
+use crate::w4;
+
+/// The maximum number of bytes that can be stored.
+pub const SIZE: usize = 1024;
+
+/// The number of bytes [`save`] stores before the value.
+pub const HEADER_SIZE: usize = 4;
+
+/// Reads stored bytes into `buf`, returning how many were read.
+///
+/// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#diskr-destptr-size)
+#[inline]
+pub fn read(buf: &mut [u8]) -> usize {
+  let size = buf.len().min(SIZE) as i32;
+  unsafe { w4::diskr(buf.as_mut_ptr(), size) as usize }
+}
+
+/// Replaces the stored bytes with `bytes`, returning how many were written.
+///
+/// Anything past the first [`SIZE`] bytes is not written.
+///
+/// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#diskw-srcptr-size)
+#[inline]
+pub fn write(bytes: &[u8]) -> usize {
+  let size = bytes.len().min(SIZE) as i32;
+  unsafe { w4::diskw(bytes.as_ptr(), size) as usize }
+}
+
+/// Stores `value` along with its version, returning whether it was written
+/// completely.
+pub fn save<T: Save>(value: &T) -> bool {
+  const { assert!(T::SIZE <= SIZE - HEADER_SIZE, "`Save::SIZE` is too big") };
+
+  let mut buf = [0; SIZE];
+  buf[0..2].copy_from_slice(&T::VERSION.to_le_bytes());
+  buf[2..4].copy_from_slice(&(T::SIZE as u16).to_le_bytes());
+
+  let mut writer = Writer::new(&mut buf[HEADER_SIZE..][..T::SIZE]);
+  value.save(&mut writer);
+
+  let len = HEADER_SIZE + T::SIZE;
+  write(&buf[..len]) == len
+}
+
+/// Loads a value stored by [`save`], or [`None`] if nothing valid is stored.
+pub fn load<T: Save>() -> Option<T> {
+  let mut buf = [0; SIZE];
+  let read = read(&mut buf);
+
+  if read < HEADER_SIZE {
+    return None;
+  }
+
+  let version = u16::from_le_bytes([buf[0], buf[1]]);
+  let len = u16::from_le_bytes([buf[2], buf[3]]) as usize;
+  let bytes = buf[HEADER_SIZE..read].get(..len)?;
+
+  T::load(version, &mut Reader::new(bytes))
+}
+
+/// A value that can be stored with [`save`] and loaded with [`load`].
+///
+/// Simple structs can implement this with [`impl_save!`](crate::impl_save).
+pub trait Save: Sized {
+  /// The version of the layout, which should change whenever it does.
+  const VERSION: u16;
+  /// The number of bytes written by [`Save::save`].
+  const SIZE: usize;
+
+  /// Writes the value in its current layout.
+  fn save(&self, writer: &mut Writer);
+
+  /// Reads a value written with the layout of `version`, or [`None`] if it
+  /// cannot be read.
+  fn load(version: u16, reader: &mut Reader) -> Option<Self>;
+}
+
+/// A value with a fixed-size, little-endian layout.
+pub trait Field: Sized {
+  /// The number of bytes in the layout.
+  const SIZE: usize;
+
+  /// Writes the value.
+  fn write(&self, writer: &mut Writer);
+
+  /// Reads a value, or [`None`] if it cannot be read.
+  fn read(reader: &mut Reader) -> Option<Self>;
+}
+
+macro_rules! impl_field {
+  ($($ty:ty),*) => {$(
+    impl Field for $ty {
+      const SIZE: usize = core::mem::size_of::<$ty>();
+
+      #[inline]
+      fn write(&self, writer: &mut Writer) {
+        writer.write_bytes(&self.to_le_bytes());
+      }
+
+      #[inline]
+      fn read(reader: &mut Reader) -> Option<Self> {
+        Some(Self::from_le_bytes(reader.read_bytes()?))
+      }
+    }
+  )*};
+}
+
+impl_field!(u8, i8, u16, i16, u32, i32, u64, i64);
+
+impl Field for bool {
+  const SIZE: usize = 1;
+
+  #[inline]
+  fn write(&self, writer: &mut Writer) {
+    writer.write(&(*self as u8));
+  }
+
+  #[inline]
+  fn read(reader: &mut Reader) -> Option<Self> {
+    match reader.read::<u8>()? {
+      0 => Some(false),
+      1 => Some(true),
+      _ => None,
+    }
+  }
+}
+
+impl<T: Field, const N: usize> Field for [T; N] {
+  const SIZE: usize = T::SIZE * N;
+
+  #[inline]
+  fn write(&self, writer: &mut Writer) {
+    for value in self {
+      value.write(writer);
+    }
+  }
+
+  fn read(reader: &mut Reader) -> Option<Self> {
+    let mut values = [const { None }; N];
+    for value in &mut values {
+      *value = Some(T::read(reader)?);
+    }
+    Some(values.map(Option::unwrap))
+  }
+}
+
+/// Writes [`Field`]s one after another.
+pub struct Writer<'a> {
+  bytes: &'a mut [u8],
+  pos: usize,
+}
+
+impl<'a> Writer<'a> {
+  /// Create a new writer starting at the beginning of `bytes`.
+  #[inline]
+  pub fn new(bytes: &'a mut [u8]) -> Self {
+    Self { bytes, pos: 0 }
+  }
+
+  /// Writes a field.
+  ///
+  /// Panics if there isn't enough space left.
+  #[inline]
+  pub fn write<T: Field>(&mut self, value: &T) {
+    value.write(self);
+  }
+
+  /// Writes raw bytes.
+  ///
+  /// Panics if there isn't enough space left.
+  #[inline]
+  pub fn write_bytes(&mut self, bytes: &[u8]) {
+    self.bytes[self.pos..][..bytes.len()].copy_from_slice(bytes);
+    self.pos += bytes.len();
+  }
+}
+
+/// Reads [`Field`]s one after another.
+pub struct Reader<'a> {
+  bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+  /// Create a new reader starting at the beginning of `bytes`.
+  #[inline]
+  pub fn new(bytes: &'a [u8]) -> Self {
+    Self { bytes }
+  }
+
+  /// Reads a field, or [`None`] if there aren't enough bytes left.
+  #[inline]
+  pub fn read<T: Field>(&mut self) -> Option<T> {
+    T::read(self)
+  }
+
+  /// Reads `N` raw bytes, or [`None`] if there aren't enough left.
+  #[inline]
+  pub fn read_bytes<const N: usize>(&mut self) -> Option<[u8; N]> {
+    let (bytes, rest) = self.bytes.split_first_chunk()?;
+    self.bytes = rest;
+    Some(*bytes)
+  }
+
+  /// The number of bytes left to read.
+  #[inline]
+  pub fn remaining(&self) -> usize {
+    self.bytes.len()
+  }
+}
+
+/// Implements [`Save`](crate::disk::Save) for a struct whose fields all
+/// implement [`Field`](crate::disk::Field).
+///
+/// The fields are stored in the order given. Saves with another version are
+/// passed to the `migrate` function if one is given, so that older saves can
+/// still be loaded after the layout changes, and are rejected otherwise.
+///
+/// ```ignore
+/// struct Progress {
+///   level: u8,
+///   score: u32,
+/// }
+///
+/// tw4f::impl_save!(
+///   Progress,
+///   version = 2,
+///   { level: u8, score: u32 },
+///   // Version 1 only stored the level.
+///   migrate = |version, reader| match version {
+///     1 => Some(Progress { level: reader.read()?, score: 0 }),
+///     _ => None,
+///   }
+/// );
+/// ```
+#[macro_export]
+macro_rules! impl_save {
+  ($ty:ty, version = $version:expr, { $($field:ident: $field_ty:ty),* $(,)? } $(,)?) => {
+    $crate::impl_save!(
+      $ty,
+      version = $version,
+      { $($field: $field_ty),* },
+      migrate = |_, _| ::core::option::Option::None
+    );
+  };
+  (
+    $ty:ty,
+    version = $version:expr,
+    { $($field:ident: $field_ty:ty),* $(,)? },
+    migrate = $migrate:expr $(,)?
+  ) => {
+    impl $crate::disk::Save for $ty {
+      const VERSION: u16 = $version;
+      const SIZE: usize =
+        0 $(+ <$field_ty as $crate::disk::Field>::SIZE)*;
+
+      fn save(&self, writer: &mut $crate::disk::Writer) {
+        $(writer.write::<$field_ty>(&self.$field);)*
+      }
+
+      fn load(
+        version: u16,
+        reader: &mut $crate::disk::Reader,
+      ) -> ::core::option::Option<Self> {
+        if version != $version {
+          let migrate: fn(
+            u16,
+            &mut $crate::disk::Reader,
+          ) -> ::core::option::Option<Self> = $migrate;
+          return migrate(version, reader);
+        }
+
+        ::core::option::Option::Some(Self {
+          $($field: reader.read::<$field_ty>()?,)*
+        })
+      }
+    }
+  };
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+  use super::*;
+  use crate::mock;
+
+  #[derive(Debug, PartialEq)]
+  struct Progress {
+    level: u8,
+    score: u32,
+    unlocked: [bool; 3],
+  }
+
+  crate::impl_save!(Progress, version = 1, {
+    level: u8,
+    score: u32,
+    unlocked: [bool; 3],
+  });
+
+  #[derive(Debug, PartialEq)]
+  struct Settings {
+    volume: u8,
+    muted: bool,
+  }
+
+  crate::impl_save!(
+    Settings,
+    version = 2,
+    { volume: u8, muted: bool },
+    migrate = |version, reader| match version {
+      // Version 1 only stored whether the sound was muted.
+      1 => Some(Settings {
+        volume: 100,
+        muted: reader.read()?,
+      }),
+      _ => None,
+    }
+  );
+
+  const PROGRESS: Progress = Progress {
+    level: 3,
+    score: 0x0102_0304,
+    unlocked: [true, false, true],
+  };
+
+  #[test]
+  fn save_load() {
+    mock::reset();
+
+    assert!(save(&PROGRESS));
+    assert_eq!(
+      mock::disk(),
+      [1, 0, 8, 0, 3, 4, 3, 2, 1, 1, 0, 1],
+      "version, length and little-endian fields"
+    );
+    assert_eq!(load(), Some(PROGRESS));
+  }
+
+  #[test]
+  fn load_empty() {
+    mock::reset();
+    assert_eq!(load::<Progress>(), None);
+
+    mock::set_disk(&[1, 0]);
+    assert_eq!(load::<Progress>(), None);
+  }
+
+  #[test]
+  fn load_other_version() {
+    mock::reset();
+    mock::set_disk(&[2, 0, 8, 0, 3, 4, 3, 2, 1, 1, 0, 1]);
+
+    assert_eq!(load::<Progress>(), None);
+  }
+
+  #[test]
+  fn load_truncated() {
+    mock::reset();
+    // The header claims 8 bytes but only 5 were stored.
+    mock::set_disk(&[1, 0, 8, 0, 3, 4, 3, 2, 1]);
+    assert_eq!(load::<Progress>(), None);
+
+    // The header is right but too short for the fields.
+    mock::set_disk(&[1, 0, 5, 0, 3, 4, 3, 2, 1]);
+    assert_eq!(load::<Progress>(), None);
+  }
+
+  #[test]
+  fn load_invalid_field() {
+    mock::reset();
+    mock::set_disk(&[1, 0, 8, 0, 3, 4, 3, 2, 1, 1, 2, 1]);
+
+    assert_eq!(load::<Progress>(), None);
+  }
+
+  #[test]
+  fn migrate() {
+    mock::reset();
+    mock::set_disk(&[1, 0, 1, 0, 1]);
+    assert_eq!(
+      load(),
+      Some(Settings {
+        volume: 100,
+        muted: true
+      })
+    );
+
+    mock::set_disk(&[0, 0, 1, 0, 1]);
+    assert_eq!(load::<Settings>(), None);
+
+    let settings = Settings {
+      volume: 40,
+      muted: false,
+    };
+    assert!(save(&settings));
+    assert_eq!(load(), Some(settings));
+  }
+
+  #[test]
+  fn write_oversized() {
+    mock::reset();
+
+    assert_eq!(write(&[7; SIZE + 1]), SIZE);
+    assert_eq!(mock::disk().len(), SIZE);
+
+    let mut buf = [0; SIZE + 1];
+    assert_eq!(read(&mut buf), SIZE);
+    assert_eq!(buf[SIZE], 0);
+  }
+}
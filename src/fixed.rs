@@ -0,0 +1,545 @@
+//! Fixed-point numbers for deterministic gameplay.
+//!
+//! Floating point results can differ between machines, which breaks the
+//! rollback used by netplay. [`Fx`] only uses integer arithmetic, so the same
+//! inputs always give the same outputs.
+//!
+//! ```ignore
+//! let mut x = Fx::from_int(80);
+//! let speed = Fx::from_ratio(3, 2);
+//!
+//! x += speed * angle.cos();
+//! shape::circle(x.round(), 80, 4);
+//! ```
+
+use core::fmt;
+use core::ops::{
+  Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign,
+};
+
+/// A signed 16.16 fixed-point number.
+///
+/// Arithmetic wraps around on overflow in both debug and release builds, so
+/// results never depend on how the game was built. Use the `checked_` methods
+/// to detect overflow instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fx(i32);
+
+impl Fx {
+  /// The number of fractional bits.
+  pub const FRAC_BITS: u32 = 16;
+
+  /// 0.
+  pub const ZERO: Self = Self(0);
+  /// 1.
+  pub const ONE: Self = Self(1 << Self::FRAC_BITS);
+  /// 0.5.
+  pub const HALF: Self = Self(1 << (Self::FRAC_BITS - 1));
+  /// The smallest positive value.
+  pub const EPSILON: Self = Self(1);
+  /// The smallest value.
+  pub const MIN: Self = Self(i32::MIN);
+  /// The largest value.
+  pub const MAX: Self = Self(i32::MAX);
+  /// π.
+  pub const PI: Self = Self(205887);
+  /// π/2.
+  pub const FRAC_PI_2: Self = Self(102944);
+  /// 2π.
+  pub const TAU: Self = Self(411775);
+
+  /// Create a new number from its raw bits.
+  #[inline]
+  pub const fn from_bits(bits: i32) -> Self {
+    Self(bits)
+  }
+
+  /// Returns the raw bits.
+  #[inline]
+  pub const fn to_bits(self) -> i32 {
+    self.0
+  }
+
+  /// Create a new number from an integer.
+  #[inline]
+  pub const fn from_int(int: i32) -> Self {
+    Self(int << Self::FRAC_BITS)
+  }
+
+  /// Create a new number from `numerator / denominator`.
+  ///
+  /// Panics if the denominator is 0.
+  #[inline]
+  pub const fn from_ratio(numerator: i32, denominator: i32) -> Self {
+    Self((((numerator as i64) << Self::FRAC_BITS) / denominator as i64) as i32)
+  }
+
+  /// The largest integer less than or equal to this.
+  #[inline]
+  pub const fn floor(self) -> i32 {
+    self.0 >> Self::FRAC_BITS
+  }
+
+  /// The smallest integer greater than or equal to this.
+  #[inline]
+  pub const fn ceil(self) -> i32 {
+    ((self.0 as i64 + Self::ONE.0 as i64 - 1) >> Self::FRAC_BITS) as i32
+  }
+
+  /// The nearest integer, rounding halves up.
+  ///
+  /// Useful for turning positions into pixel coordinates.
+  #[inline]
+  pub const fn round(self) -> i32 {
+    ((self.0 as i64 + Self::HALF.0 as i64) >> Self::FRAC_BITS) as i32
+  }
+
+  /// The part after the point, which is never negative.
+  #[inline]
+  pub const fn fract(self) -> Self {
+    Self(self.0 & (Self::ONE.0 - 1))
+  }
+
+  /// The absolute value.
+  ///
+  /// [`Fx::MIN`] wraps around to itself.
+  #[inline]
+  pub const fn abs(self) -> Self {
+    Self(self.0.wrapping_abs())
+  }
+
+  /// -1, 0 or 1 depending on the sign.
+  #[inline]
+  pub const fn signum(self) -> Self {
+    Self::from_int(self.0.signum())
+  }
+
+  /// Whether this is less than 0.
+  #[inline]
+  pub const fn is_negative(self) -> bool {
+    self.0 < 0
+  }
+
+  /// Adds `rhs`, or returns `None` on overflow.
+  #[inline]
+  pub const fn checked_add(self, rhs: Self) -> Option<Self> {
+    match self.0.checked_add(rhs.0) {
+      Some(bits) => Some(Self(bits)),
+      None => None,
+    }
+  }
+
+  /// Subtracts `rhs`, or returns `None` on overflow.
+  #[inline]
+  pub const fn checked_sub(self, rhs: Self) -> Option<Self> {
+    match self.0.checked_sub(rhs.0) {
+      Some(bits) => Some(Self(bits)),
+      None => None,
+    }
+  }
+
+  /// Multiplies by `rhs`, or returns `None` on overflow.
+  #[inline]
+  pub const fn checked_mul(self, rhs: Self) -> Option<Self> {
+    let bits = (self.0 as i64 * rhs.0 as i64) >> Self::FRAC_BITS;
+    match bits >= i32::MIN as i64 && bits <= i32::MAX as i64 {
+      true => Some(Self(bits as i32)),
+      false => None,
+    }
+  }
+
+  /// Divides by `rhs`, or returns `None` if `rhs` is 0 or on overflow.
+  #[inline]
+  pub const fn checked_div(self, rhs: Self) -> Option<Self> {
+    if rhs.0 == 0 {
+      return None;
+    }
+    let bits = ((self.0 as i64) << Self::FRAC_BITS) / rhs.0 as i64;
+    match bits >= i32::MIN as i64 && bits <= i32::MAX as i64 {
+      true => Some(Self(bits as i32)),
+      false => None,
+    }
+  }
+
+  /// Linearly interpolates from this to `other`, where `t` is from 0 to 1.
+  #[inline]
+  pub fn lerp(self, other: Self, t: Self) -> Self {
+    self + (other - self) * t
+  }
+
+  /// The square root, or 0 for negative numbers.
+  #[inline]
+  pub const fn sqrt(self) -> Self {
+    match self.0 {
+      ..=0 => Self::ZERO,
+      bits => Self(((bits as u64) << Self::FRAC_BITS).isqrt() as i32),
+    }
+  }
+
+  /// The sine of an angle in radians.
+  pub const fn sin(self) -> Self {
+    // Converts radians into a 16-bit fraction of a turn.
+    let turn = ((self.0 as i64 * 683565276) >> 32) as u32 & 0xffff;
+    let within = turn & 0x3fff;
+
+    Self(match turn >> 14 {
+      0 => quarter_sin(within),
+      1 => quarter_sin(0x4000 - within),
+      2 => -quarter_sin(within),
+      _ => -quarter_sin(0x4000 - within),
+    })
+  }
+
+  /// The cosine of an angle in radians.
+  #[inline]
+  pub const fn cos(self) -> Self {
+    Self(self.0.wrapping_add(Self::FRAC_PI_2.0)).sin()
+  }
+
+  /// The angle in radians from the positive X axis to the point (`x`,
+  /// `self`), from -π to π.
+  pub const fn atan2(self, x: Self) -> Self {
+    let (y, x) = (self.0 as i64, x.0 as i64);
+    if y == 0 && x == 0 {
+      return Self::ZERO;
+    }
+
+    let (ay, ax) = (y.abs(), x.abs());
+    let mut angle = match ay <= ax {
+      true => atan_unit(((ay << 16) / ax) as u32),
+      false => Self::FRAC_PI_2.0 - atan_unit(((ax << 16) / ay) as u32),
+    };
+    if x < 0 {
+      angle = Self::PI.0 - angle;
+    }
+    if y < 0 {
+      angle = -angle;
+    }
+
+    Self(angle)
+  }
+}
+
+/// Looks up `sin` for a quarter turn split into 16384 steps.
+const fn quarter_sin(step: u32) -> i32 {
+  let (index, frac) = ((step >> 6) as usize, (step & 63) as i32);
+  match frac {
+    0 => SIN[index],
+    _ => SIN[index] + (((SIN[index + 1] - SIN[index]) * frac) >> 6),
+  }
+}
+
+/// Looks up `atan` for a ratio from 0 to 1 in 16.16.
+const fn atan_unit(ratio: u32) -> i32 {
+  let (index, frac) = ((ratio >> 8) as usize, (ratio & 255) as i32);
+  match frac {
+    0 => ATAN[index],
+    _ => ATAN[index] + (((ATAN[index + 1] - ATAN[index]) * frac) >> 8),
+  }
+}
+
+/// `sin` from 0 to π/2 in 256 steps, in 16.16.
+const SIN: [i32; 257] = [
+  0, 402, 804, 1206, 1608, 2010, 2412, 2814, 3216, 3617, 4019, 4420, 4821,
+  5222, 5623, 6023, 6424, 6824, 7224, 7623, 8022, 8421, 8820, 9218, 9616,
+  10014, 10411, 10808, 11204, 11600, 11996, 12391, 12785, 13180, 13573, 13966,
+  14359, 14751, 15143, 15534, 15924, 16314, 16703, 17091, 17479, 17867, 18253,
+  18639, 19024, 19409, 19792, 20175, 20557, 20939, 21320, 21699, 22078, 22457,
+  22834, 23210, 23586, 23961, 24335, 24708, 25080, 25451, 25821, 26190, 26558,
+  26925, 27291, 27656, 28020, 28383, 28745, 29106, 29466, 29824, 30182, 30538,
+  30893, 31248, 31600, 31952, 32303, 32652, 33000, 33347, 33692, 34037, 34380,
+  34721, 35062, 35401, 35738, 36075, 36410, 36744, 37076, 37407, 37736, 38064,
+  38391, 38716, 39040, 39362, 39683, 40002, 40320, 40636, 40951, 41264, 41576,
+  41886, 42194, 42501, 42806, 43110, 43412, 43713, 44011, 44308, 44604, 44898,
+  45190, 45480, 45769, 46056, 46341, 46624, 46906, 47186, 47464, 47741, 48015,
+  48288, 48559, 48828, 49095, 49361, 49624, 49886, 50146, 50404, 50660, 50914,
+  51166, 51417, 51665, 51911, 52156, 52398, 52639, 52878, 53114, 53349, 53581,
+  53812, 54040, 54267, 54491, 54714, 54934, 55152, 55368, 55582, 55794, 56004,
+  56212, 56418, 56621, 56823, 57022, 57219, 57414, 57607, 57798, 57986, 58172,
+  58356, 58538, 58718, 58896, 59071, 59244, 59415, 59583, 59750, 59914, 60075,
+  60235, 60392, 60547, 60700, 60851, 60999, 61145, 61288, 61429, 61568, 61705,
+  61839, 61971, 62101, 62228, 62353, 62476, 62596, 62714, 62830, 62943, 63054,
+  63162, 63268, 63372, 63473, 63572, 63668, 63763, 63854, 63944, 64031, 64115,
+  64197, 64277, 64354, 64429, 64501, 64571, 64639, 64704, 64766, 64827, 64884,
+  64940, 64993, 65043, 65091, 65137, 65180, 65220, 65259, 65294, 65328, 65358,
+  65387, 65413, 65436, 65457, 65476, 65492, 65505, 65516, 65525, 65531, 65535,
+  65536,
+];
+
+/// `atan` from 0 to 1 in 256 steps, in 16.16.
+const ATAN: [i32; 257] = [
+  0, 256, 512, 768, 1024, 1280, 1536, 1792, 2047, 2303, 2559, 2814, 3070, 3325,
+  3580, 3836, 4091, 4346, 4600, 4855, 5110, 5364, 5618, 5872, 6126, 6380, 6633,
+  6887, 7140, 7392, 7645, 7898, 8150, 8402, 8653, 8905, 9156, 9407, 9657, 9908,
+  10158, 10408, 10657, 10906, 11155, 11403, 11652, 11899, 12147, 12394, 12641,
+  12887, 13133, 13379, 13624, 13869, 14114, 14358, 14601, 14845, 15088, 15330,
+  15572, 15814, 16055, 16296, 16536, 16776, 17015, 17254, 17492, 17730, 17968,
+  18205, 18441, 18677, 18913, 19148, 19382, 19616, 19850, 20083, 20315, 20547,
+  20779, 21009, 21240, 21469, 21699, 21927, 22156, 22383, 22610, 22836, 23062,
+  23288, 23512, 23737, 23960, 24183, 24406, 24627, 24849, 25069, 25289, 25509,
+  25727, 25946, 26163, 26380, 26597, 26813, 27028, 27242, 27456, 27670, 27882,
+  28094, 28306, 28517, 28727, 28936, 29145, 29354, 29561, 29768, 29975, 30180,
+  30386, 30590, 30794, 30997, 31200, 31402, 31603, 31803, 32003, 32203, 32401,
+  32600, 32797, 32994, 33190, 33385, 33580, 33774, 33968, 34160, 34353, 34544,
+  34735, 34925, 35115, 35304, 35492, 35680, 35867, 36053, 36239, 36424, 36608,
+  36792, 36975, 37158, 37340, 37521, 37701, 37881, 38060, 38239, 38417, 38594,
+  38771, 38947, 39123, 39297, 39472, 39645, 39818, 39990, 40162, 40333, 40503,
+  40673, 40842, 41010, 41178, 41346, 41512, 41678, 41844, 42008, 42172, 42336,
+  42499, 42661, 42823, 42984, 43145, 43304, 43464, 43622, 43780, 43938, 44095,
+  44251, 44407, 44562, 44716, 44870, 45024, 45176, 45328, 45480, 45631, 45781,
+  45931, 46080, 46229, 46377, 46525, 46672, 46818, 46964, 47109, 47254, 47398,
+  47542, 47685, 47827, 47969, 48111, 48251, 48392, 48531, 48671, 48809, 48947,
+  49085, 49222, 49359, 49495, 49630, 49765, 49899, 50033, 50167, 50299, 50432,
+  50563, 50695, 50826, 50956, 51086, 51215, 51344, 51472,
+];
+
+impl From<i32> for Fx {
+  #[inline]
+  fn from(int: i32) -> Self {
+    Self::from_int(int)
+  }
+}
+
+impl fmt::Display for Fx {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Display::fmt(&(self.0 as f64 / Self::ONE.0 as f64), f)
+  }
+}
+
+impl Neg for Fx {
+  type Output = Self;
+
+  #[inline]
+  fn neg(self) -> Self {
+    Self(self.0.wrapping_neg())
+  }
+}
+
+impl Add for Fx {
+  type Output = Self;
+
+  #[inline]
+  fn add(self, rhs: Self) -> Self {
+    Self(self.0.wrapping_add(rhs.0))
+  }
+}
+
+impl Sub for Fx {
+  type Output = Self;
+
+  #[inline]
+  fn sub(self, rhs: Self) -> Self {
+    Self(self.0.wrapping_sub(rhs.0))
+  }
+}
+
+impl Mul for Fx {
+  type Output = Self;
+
+  #[inline]
+  fn mul(self, rhs: Self) -> Self {
+    // The product always fits in an `i64`, and the cast wraps it into range.
+    Self(((self.0 as i64 * rhs.0 as i64) >> Self::FRAC_BITS) as i32)
+  }
+}
+
+impl Div for Fx {
+  type Output = Self;
+
+  /// Panics if `rhs` is 0.
+  #[inline]
+  fn div(self, rhs: Self) -> Self {
+    Self((((self.0 as i64) << Self::FRAC_BITS) / rhs.0 as i64) as i32)
+  }
+}
+
+impl Mul<i32> for Fx {
+  type Output = Self;
+
+  #[inline]
+  fn mul(self, rhs: i32) -> Self {
+    Self(self.0.wrapping_mul(rhs))
+  }
+}
+
+impl Div<i32> for Fx {
+  type Output = Self;
+
+  /// Panics if `rhs` is 0.
+  #[inline]
+  fn div(self, rhs: i32) -> Self {
+    Self(self.0.wrapping_div(rhs))
+  }
+}
+
+impl AddAssign for Fx {
+  #[inline]
+  fn add_assign(&mut self, rhs: Self) {
+    *self = *self + rhs;
+  }
+}
+
+impl SubAssign for Fx {
+  #[inline]
+  fn sub_assign(&mut self, rhs: Self) {
+    *self = *self - rhs;
+  }
+}
+
+impl MulAssign for Fx {
+  #[inline]
+  fn mul_assign(&mut self, rhs: Self) {
+    *self = *self * rhs;
+  }
+}
+
+impl DivAssign for Fx {
+  #[inline]
+  fn div_assign(&mut self, rhs: Self) {
+    *self = *self / rhs;
+  }
+}
+
+impl MulAssign<i32> for Fx {
+  #[inline]
+  fn mul_assign(&mut self, rhs: i32) {
+    *self = *self * rhs;
+  }
+}
+
+impl DivAssign<i32> for Fx {
+  #[inline]
+  fn div_assign(&mut self, rhs: i32) {
+    *self = *self / rhs;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn float(x: Fx) -> f64 {
+    x.to_bits() as f64 / 65536.0
+  }
+
+  fn fx(x: f64) -> Fx {
+    Fx::from_bits((x * 65536.0).round() as i32)
+  }
+
+  #[track_caller]
+  fn assert_near(actual: Fx, expected: f64) {
+    let error = (float(actual) - expected).abs();
+    assert!(
+      error < 0.0002,
+      "{} is not close to {expected}",
+      float(actual)
+    );
+  }
+
+  #[test]
+  fn sin_cos_key_angles() {
+    use core::f64::consts::{FRAC_PI_2, FRAC_PI_4, FRAC_PI_6, PI};
+
+    for angle in [0.0, FRAC_PI_6, FRAC_PI_4, FRAC_PI_2, PI, 1.5 * PI] {
+      assert_near(fx(angle).sin(), angle.sin());
+      assert_near(fx(angle).cos(), angle.cos());
+    }
+    assert_eq!(Fx::ZERO.sin(), Fx::ZERO);
+    assert_eq!(Fx::ZERO.cos(), Fx::ONE);
+    assert_eq!(Fx::FRAC_PI_2.sin(), Fx::ONE);
+  }
+
+  #[test]
+  fn sin_cos_wrap() {
+    use core::f64::consts::{FRAC_PI_4, PI, TAU};
+
+    for angle in [-FRAC_PI_4, -PI / 2.0, -PI, -3.0, TAU + 1.0, 5.0 * PI, 100.0]
+    {
+      assert_near(fx(angle).sin(), angle.sin());
+      assert_near(fx(angle).cos(), angle.cos());
+    }
+  }
+
+  #[test]
+  fn atan2_quadrants() {
+    use core::f64::consts::FRAC_PI_4;
+
+    let atan2 = |y: f64, x: f64| fx(y).atan2(fx(x));
+
+    assert_near(atan2(1.0, 1.0), FRAC_PI_4);
+    assert_near(atan2(1.0, -1.0), 3.0 * FRAC_PI_4);
+    assert_near(atan2(-1.0, -1.0), -3.0 * FRAC_PI_4);
+    assert_near(atan2(-1.0, 1.0), -FRAC_PI_4);
+    assert_near(atan2(2.0, 7.0), 2f64.atan2(7.0));
+    assert_near(atan2(-7.0, -2.0), (-7f64).atan2(-2.0));
+  }
+
+  #[test]
+  fn atan2_axes() {
+    let atan2 = |y: i32, x: i32| Fx::from_int(y).atan2(Fx::from_int(x));
+
+    assert_eq!(atan2(0, 0), Fx::ZERO);
+    assert_eq!(atan2(0, 5), Fx::ZERO);
+    assert_eq!(atan2(5, 0), Fx::FRAC_PI_2);
+    assert_eq!(atan2(0, -5), Fx::PI);
+    assert_eq!(atan2(-5, 0), -Fx::FRAC_PI_2);
+  }
+
+  #[test]
+  fn sqrt() {
+    assert_eq!(Fx::from_int(4).sqrt(), Fx::from_int(2));
+    assert_eq!(Fx::from_ratio(1, 4).sqrt(), Fx::HALF);
+    assert_eq!(Fx::from_int(2).sqrt().to_bits(), 92681);
+    assert_eq!(Fx::ZERO.sqrt(), Fx::ZERO);
+    assert_eq!(Fx::from_int(-4).sqrt(), Fx::ZERO);
+    assert_near(Fx::MAX.sqrt(), 181.0193);
+  }
+
+  #[test]
+  fn rounding() {
+    let x = Fx::from_ratio(5, 2);
+    assert_eq!((x.floor(), x.ceil(), x.round()), (2, 3, 3));
+
+    let x = Fx::from_ratio(-5, 2);
+    assert_eq!((x.floor(), x.ceil(), x.round()), (-3, -2, -2));
+
+    let x = Fx::from_ratio(-13, 10);
+    assert_eq!((x.floor(), x.ceil(), x.round()), (-2, -1, -1));
+
+    let x = Fx::from_ratio(-17, 10);
+    assert_eq!((x.floor(), x.ceil(), x.round()), (-2, -1, -2));
+
+    let x = Fx::from_int(-3);
+    assert_eq!((x.floor(), x.ceil(), x.round()), (-3, -3, -3));
+    assert_eq!(Fx::MAX.ceil(), 32768);
+    assert_eq!(Fx::MIN.floor(), -32768);
+  }
+
+  #[test]
+  fn overflow_wraps() {
+    assert_eq!(Fx::MAX + Fx::EPSILON, Fx::MIN);
+    assert_eq!(Fx::MIN - Fx::EPSILON, Fx::MAX);
+    assert_eq!(-Fx::MIN, Fx::MIN);
+    assert_eq!(Fx::MIN.abs(), Fx::MIN);
+    assert_eq!(Fx::MAX * Fx::from_int(2), Fx::from_bits(-2));
+    assert_eq!(Fx::MIN / -Fx::ONE, Fx::MIN);
+    assert_eq!(Fx::MAX * 2, Fx::from_bits(-2));
+    assert_eq!(Fx::MIN / -1, Fx::MIN);
+    assert_eq!(Fx::from_int(-3).abs(), Fx::from_int(3));
+  }
+
+  #[test]
+  fn checked() {
+    let two = Fx::from_int(2);
+
+    assert_eq!(Fx::ONE.checked_add(Fx::ONE), Some(two));
+    assert_eq!(Fx::MAX.checked_add(Fx::EPSILON), None);
+    assert_eq!(Fx::MIN.checked_sub(Fx::EPSILON), None);
+    assert_eq!(Fx::HALF.checked_mul(two), Some(Fx::ONE));
+    assert_eq!(Fx::MAX.checked_mul(two), None);
+    assert_eq!(Fx::MIN.checked_mul(-Fx::ONE), None);
+    assert_eq!(Fx::ONE.checked_div(Fx::HALF), Some(two));
+    assert_eq!(Fx::ONE.checked_div(Fx::ZERO), None);
+    assert_eq!(Fx::MIN.checked_div(-Fx::ONE), None);
+  }
+}
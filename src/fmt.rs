@@ -0,0 +1,183 @@
+//! Formatting without an allocator.
+
+use core::ffi::CStr;
+use core::fmt;
+
+use crate::w4;
+
+/// A fixed-size buffer on the stack that text can be formatted into.
+///
+/// Text that doesn't fit is cut off at the last whole character and the
+/// buffer is marked as truncated.
+#[derive(Clone)]
+pub struct Buffer<const N: usize> {
+  bytes: [u8; N],
+  len: usize,
+  truncated: bool,
+}
+
+impl<const N: usize> Default for Buffer<N> {
+  #[inline]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<const N: usize> fmt::Debug for Buffer<N> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Debug::fmt(self.as_str(), f)
+  }
+}
+
+impl<const N: usize> Buffer<N> {
+  /// Create a new, empty buffer.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      bytes: [0; N],
+      len: 0,
+      truncated: false,
+    }
+  }
+
+  /// The text written so far.
+  #[inline]
+  pub fn as_str(&self) -> &str {
+    // Only whole `str`s or prefixes ending on a character boundary are
+    // ever copied in.
+    unsafe { core::str::from_utf8_unchecked(&self.bytes[..self.len]) }
+  }
+
+  /// Whether any text has been cut off.
+  #[inline]
+  pub fn is_truncated(&self) -> bool {
+    self.truncated
+  }
+
+  /// Empties the buffer.
+  #[inline]
+  pub fn clear(&mut self) {
+    self.len = 0;
+    self.truncated = false;
+  }
+}
+
+impl<const N: usize> fmt::Write for Buffer<N> {
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    let space = N - self.len;
+    let mut len = s.len().min(space);
+
+    while !s.is_char_boundary(len) {
+      len -= 1;
+    }
+
+    self.bytes[self.len..][..len].copy_from_slice(&s.as_bytes()[..len]);
+    self.len += len;
+
+    if len < s.len() {
+      self.truncated = true;
+      return Err(fmt::Error);
+    }
+    Ok(())
+  }
+}
+
+/// An argument to [`tracef`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Arg<'a> {
+  /// An integer, for `%d` and `%x`.
+  Int(i32),
+  /// A character, for `%c`.
+  Char(char),
+  /// A floating-point number, for `%f`.
+  Float(f64),
+  /// A string, for `%s`.
+  Str(&'a CStr),
+}
+
+/// Debug prints text to the terminal using the console's own formatting.
+///
+/// `%d`, `%x`, `%c`, `%f` and `%s` are replaced by `args` in order, and `%%`
+/// by a single `%`.
+///
+/// Panics if `args` doesn't match the format, or there are more than 32.
+///
+/// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#tracef-fmt-args)
+pub fn tracef(format: &CStr, args: &[Arg]) {
+  assert!(args.len() <= 32, "too many arguments");
+
+  let mut buf = [0u8; 256];
+  let mut len = 0;
+  let mut push = |bytes: &[u8]| {
+    buf[len..][..bytes.len()].copy_from_slice(bytes);
+    len += bytes.len();
+  };
+
+  let mut args = args.iter();
+  let mut specifiers = format.to_bytes().iter();
+
+  while let Some(byte) = specifiers.next() {
+    if *byte != b'%' {
+      continue;
+    }
+
+    let specifier = specifiers.next();
+    if specifier == Some(&b'%') {
+      continue;
+    }
+
+    match (specifier, args.next()) {
+      (Some(b'd' | b'x'), Some(Arg::Int(int))) => push(&int.to_le_bytes()),
+      (Some(b'c'), Some(Arg::Char(char))) => {
+        push(&(*char as u32).to_le_bytes())
+      }
+      (Some(b'f'), Some(Arg::Float(float))) => push(&float.to_le_bytes()),
+      (Some(b's'), Some(Arg::Str(str))) => {
+        push(&(str.as_ptr() as usize).to_le_bytes())
+      }
+      _ => panic!("arguments do not match the format"),
+    }
+  }
+
+  assert!(args.next().is_none(), "arguments do not match the format");
+
+  unsafe { w4::tracef(format.as_ptr().cast(), buf.as_ptr()) }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+  use super::*;
+  use crate::mock;
+
+  #[test]
+  fn tracef_percent() {
+    mock::reset();
+    tracef(c"100%% %d", &[Arg::Int(5)]);
+    tracef(c"%%", &[]);
+
+    assert_eq!(mock::traces(), ["100% 5", "%"]);
+  }
+
+  #[test]
+  fn tracef_args() {
+    mock::reset();
+    tracef(
+      c"%d %x %c %f %s",
+      &[
+        Arg::Int(-3),
+        Arg::Int(255),
+        Arg::Char('w'),
+        Arg::Float(0.25),
+        Arg::Str(c"hi"),
+      ],
+    );
+
+    assert_eq!(mock::traces(), ["-3 ff w 0.25 hi"]);
+  }
+
+  #[test]
+  #[should_panic = "arguments do not match the format"]
+  fn tracef_mismatch() {
+    tracef(c"%d", &[Arg::Char('w')]);
+  }
+}
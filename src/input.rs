@@ -0,0 +1,305 @@
+//! Edge-triggered input built on top of [`Gamepad`] and [`Mouse`].
+
+use crate::{ptr, Gamepad, Mouse, Player};
+
+/// A snapshot of every gamepad and the mouse, compared against the previous
+/// frame.
+///
+/// Call [`Input::update`] once at the start of every frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Input {
+  gamepads: [u8; 4],
+  prev_gamepads: [u8; 4],
+  gamepad_frames: [[u16; 8]; 4],
+  mouse: u8,
+  prev_mouse: u8,
+  mouse_frames: [u16; 3],
+  mouse_x: i16,
+  mouse_y: i16,
+}
+
+impl Default for Input {
+  #[inline]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Input {
+  /// Create a new snapshot with nothing held.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      gamepads: [0; 4],
+      prev_gamepads: [0; 4],
+      gamepad_frames: [[0; 8]; 4],
+      mouse: 0,
+      prev_mouse: 0,
+      mouse_frames: [0; 3],
+      mouse_x: 0,
+      mouse_y: 0,
+    }
+  }
+
+  /// Takes a new snapshot, which should happen once per frame.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory#gamepads)
+  pub fn update(&mut self) {
+    self.prev_gamepads = self.gamepads;
+    self.prev_mouse = self.mouse;
+
+    unsafe {
+      self.gamepads = *ptr::<[u8; 4]>(Gamepad::GAMEPADS);
+      self.mouse = *ptr::<u8>(Mouse::MOUSE_BUTTONS);
+      [self.mouse_x, self.mouse_y] = *ptr::<[i16; 2]>(Mouse::MOUSE_POSITION);
+    }
+
+    for (buttons, frames) in self.gamepads.iter().zip(&mut self.gamepad_frames)
+    {
+      count(*buttons, frames);
+    }
+    count(self.mouse, &mut self.mouse_frames);
+  }
+
+  /// Whether the button is held by the player this frame.
+  #[inline]
+  pub fn pressed(&self, button: Gamepad, player: Player) -> bool {
+    self.gamepads[player as usize] & button as u8 != 0
+  }
+
+  /// Whether the button started being held by the player this frame.
+  #[inline]
+  pub fn just_pressed(&self, button: Gamepad, player: Player) -> bool {
+    let (now, prev) = self.gamepad(button, player);
+    now && !prev
+  }
+
+  /// Whether the button stopped being held by the player this frame.
+  #[inline]
+  pub fn just_released(&self, button: Gamepad, player: Player) -> bool {
+    let (now, prev) = self.gamepad(button, player);
+    !now && prev
+  }
+
+  /// The number of frames the button has been held by the player, including
+  /// this one.
+  #[inline]
+  pub fn held_frames(&self, button: Gamepad, player: Player) -> u16 {
+    self.gamepad_frames[player as usize][bit(button as u8)]
+  }
+
+  /// Whether the button was just pressed, or has been held for `delay` frames
+  /// and then every `interval` frames after.
+  ///
+  /// Useful for scrolling through menus.
+  #[inline]
+  pub fn repeat(
+    &self,
+    button: Gamepad,
+    player: Player,
+    delay: u16,
+    interval: u16,
+  ) -> bool {
+    repeats(self.held_frames(button, player), delay, interval)
+  }
+
+  /// Whether the mouse button is held this frame.
+  #[inline]
+  pub fn mouse_pressed(&self, button: Mouse) -> bool {
+    self.mouse & button as u8 != 0
+  }
+
+  /// Whether the mouse button started being held this frame.
+  #[inline]
+  pub fn mouse_just_pressed(&self, button: Mouse) -> bool {
+    let (now, prev) = self.mouse(button);
+    now && !prev
+  }
+
+  /// Whether the mouse button stopped being held this frame.
+  #[inline]
+  pub fn mouse_just_released(&self, button: Mouse) -> bool {
+    let (now, prev) = self.mouse(button);
+    !now && prev
+  }
+
+  /// The number of frames the mouse button has been held, including this
+  /// one.
+  #[inline]
+  pub fn mouse_held_frames(&self, button: Mouse) -> u16 {
+    self.mouse_frames[bit(button as u8)]
+  }
+
+  /// Whether the mouse button was just pressed, or has been held for `delay`
+  /// frames and then every `interval` frames after.
+  #[inline]
+  pub fn mouse_repeat(&self, button: Mouse, delay: u16, interval: u16) -> bool {
+    repeats(self.mouse_held_frames(button), delay, interval)
+  }
+
+  /// The X position of the mouse this frame.
+  #[inline]
+  pub fn mouse_x(&self) -> i16 {
+    self.mouse_x
+  }
+
+  /// The Y position of the mouse this frame.
+  #[inline]
+  pub fn mouse_y(&self) -> i16 {
+    self.mouse_y
+  }
+
+  #[inline]
+  fn gamepad(&self, button: Gamepad, player: Player) -> (bool, bool) {
+    let mask = button as u8;
+    (
+      self.gamepads[player as usize] & mask != 0,
+      self.prev_gamepads[player as usize] & mask != 0,
+    )
+  }
+
+  #[inline]
+  fn mouse(&self, button: Mouse) -> (bool, bool) {
+    let mask = button as u8;
+    (self.mouse & mask != 0, self.prev_mouse & mask != 0)
+  }
+}
+
+/// Returns the index of the single bit set in `mask`.
+#[inline]
+fn bit(mask: u8) -> usize {
+  mask.trailing_zeros() as usize
+}
+
+/// Advances the held frame counts of each bit in `buttons`.
+#[inline]
+fn count(buttons: u8, frames: &mut [u16]) {
+  for (i, frames) in frames.iter_mut().enumerate() {
+    *frames = match buttons & (1 << i) {
+      0 => 0,
+      _ => frames.saturating_add(1),
+    };
+  }
+}
+
+#[inline]
+fn repeats(held: u16, delay: u16, interval: u16) -> bool {
+  match held {
+    0 => false,
+    1 => true,
+    held => held > delay && (held - 1 - delay).is_multiple_of(interval.max(1)),
+  }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+  use super::*;
+  use crate::mock;
+
+  /// Updates `input` once for each frame of `buttons` held by player 2.
+  fn frames(input: &mut Input, buttons: &[u8]) {
+    for &buttons in buttons {
+      mock::set_gamepad(Player::P2, buttons);
+      input.update();
+    }
+  }
+
+  #[test]
+  fn just_pressed_and_released() {
+    mock::reset();
+    let mut input = Input::new();
+    let x = Gamepad::X as u8;
+
+    frames(&mut input, &[x]);
+    assert!(input.just_pressed(Gamepad::X, Player::P2));
+    assert!(input.pressed(Gamepad::X, Player::P2));
+    assert!(!input.just_pressed(Gamepad::X, Player::P1));
+    assert!(!input.just_pressed(Gamepad::Z, Player::P2));
+
+    frames(&mut input, &[x]);
+    assert!(!input.just_pressed(Gamepad::X, Player::P2));
+    assert!(!input.just_released(Gamepad::X, Player::P2));
+
+    frames(&mut input, &[0]);
+    assert!(input.just_released(Gamepad::X, Player::P2));
+    assert!(!input.pressed(Gamepad::X, Player::P2));
+
+    frames(&mut input, &[0]);
+    assert!(!input.just_released(Gamepad::X, Player::P2));
+  }
+
+  #[test]
+  fn held_frames() {
+    mock::reset();
+    let mut input = Input::new();
+    let down = Gamepad::Down as u8;
+
+    assert_eq!(input.held_frames(Gamepad::Down, Player::P2), 0);
+    frames(&mut input, &[down, down, down]);
+    assert_eq!(input.held_frames(Gamepad::Down, Player::P2), 3);
+    assert_eq!(input.held_frames(Gamepad::Up, Player::P2), 0);
+
+    frames(&mut input, &[0]);
+    assert_eq!(input.held_frames(Gamepad::Down, Player::P2), 0);
+    frames(&mut input, &[down]);
+    assert_eq!(input.held_frames(Gamepad::Down, Player::P2), 1);
+  }
+
+  #[test]
+  fn repeat() {
+    mock::reset();
+    let mut input = Input::new();
+    let left = Gamepad::Left as u8;
+
+    let mut repeated = std::vec::Vec::new();
+    for frame in 1..=20 {
+      frames(&mut input, &[left]);
+      if input.repeat(Gamepad::Left, Player::P2, 10, 4) {
+        repeated.push(frame);
+      }
+    }
+
+    // Once when pressed, then after the delay and every interval after.
+    assert_eq!(repeated, [1, 11, 15, 19]);
+  }
+
+  #[test]
+  fn repeat_no_interval() {
+    mock::reset();
+    let mut input = Input::new();
+    let left = Gamepad::Left as u8;
+
+    let mut repeated = std::vec::Vec::new();
+    for frame in 1..=6 {
+      frames(&mut input, &[left]);
+      if input.repeat(Gamepad::Left, Player::P2, 3, 0) {
+        repeated.push(frame);
+      }
+    }
+
+    assert_eq!(repeated, [1, 4, 5, 6]);
+  }
+
+  #[test]
+  fn mouse() {
+    mock::reset();
+    let mut input = Input::new();
+
+    mock::set_mouse(12, -3, Mouse::Left as u8 | Mouse::Middle as u8);
+    input.update();
+    assert!(input.mouse_just_pressed(Mouse::Left));
+    assert!(input.mouse_pressed(Mouse::Middle));
+    assert!(!input.mouse_pressed(Mouse::Right));
+    assert_eq!((input.mouse_x(), input.mouse_y()), (12, -3));
+
+    mock::set_mouse(12, -3, Mouse::Middle as u8);
+    input.update();
+    assert!(input.mouse_just_released(Mouse::Left));
+    assert!(!input.mouse_just_pressed(Mouse::Middle));
+    assert_eq!(input.mouse_held_frames(Mouse::Middle), 2);
+    assert!(!input.mouse_repeat(Mouse::Middle, 2, 1));
+
+    input.update();
+    assert!(input.mouse_repeat(Mouse::Middle, 2, 1));
+  }
+}
@@ -5,8 +5,73 @@
 #![no_std]
 #![deny(missing_docs)]
 
+#[cfg(feature = "mock")]
+extern crate std;
+
+/// Implements `ALL` and `TryFrom<u8>` for a fieldless `#[repr(u8)]` enum.
+macro_rules! impl_variants {
+  ($(#[$meta:meta])* $ty:ident { $($variant:ident),+ $(,)? }) => {
+    impl $ty {
+      $(#[$meta])*
+      pub const ALL: [Self; [$(stringify!($variant)),+].len()] =
+        [$(Self::$variant),+];
+    }
+
+    impl TryFrom<u8> for $ty {
+      type Error = $crate::InvalidValue;
+
+      #[inline]
+      fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+          $(v if v == Self::$variant as u8 => Ok(Self::$variant),)+
+          _ => Err($crate::InvalidValue(value)),
+        }
+      }
+    }
+  };
+}
+
+pub mod animation;
+pub mod color;
+pub mod disk;
+pub mod fixed;
+pub mod fmt;
+pub mod input;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod music;
+pub mod note;
+pub mod palette;
+pub mod pattern;
+pub mod sfx;
+pub mod shape;
+pub mod sprite;
+pub mod tilemap;
+pub mod tone;
+
+/// Returns a pointer to `addr` within the console's memory.
+#[inline]
+fn ptr<T>(addr: usize) -> *mut T {
+  #[cfg(not(feature = "mock"))]
+  return addr as *mut T;
+  #[cfg(feature = "mock")]
+  return mock::memory().wrapping_add(addr).cast();
+}
+
+/// The error returned when converting a `u8` that doesn't match any variant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct InvalidValue(pub u8);
+
+impl core::fmt::Display for InvalidValue {
+  fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+    write!(f, "invalid value: {}", self.0)
+  }
+}
+
+impl core::error::Error for InvalidValue {}
+
 /// Queries the current state of the gamepads.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum Gamepad {
   /// The X button.
@@ -25,22 +90,31 @@ pub enum Gamepad {
 }
 
 impl Gamepad {
-  const GAMEPADS: *const [u8; 4] = 0x16 as *const [u8; 4];
+  const GAMEPADS: usize = 0x16;
 
   /// Whether this button is currently being pressed.
   ///
   /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory#gamepads)
   #[inline]
   pub fn pressed(self, player: Player) -> bool {
-    unsafe { (*Self::GAMEPADS)[player as usize] & self as u8 == self as u8 }
+    unsafe {
+      (*ptr::<[u8; 4]>(Self::GAMEPADS))[player as usize] & self as u8
+        == self as u8
+    }
   }
 }
 
+impl_variants!(
+  /// Every button, from lowest to highest bit.
+  Gamepad { X, Z, Left, Right, Up, Down }
+);
+
 /// Useful for situations involving a specific player.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum Player {
   /// Player 1.
+  #[default]
   P1 = 0,
   /// Player 2.
   P2,
@@ -50,8 +124,65 @@ pub enum Player {
   P4,
 }
 
+impl Player {
+  /// Converts the two lowest bits into a player.
+  #[inline]
+  const fn from_index(index: u8) -> Self {
+    match index & 0b11 {
+      0 => Self::P1,
+      1 => Self::P2,
+      2 => Self::P3,
+      _ => Self::P4,
+    }
+  }
+
+  /// Whether this player is playing on this device.
+  ///
+  /// Every player is local unless a netplay session is active.
+  #[inline]
+  pub fn is_local(self) -> bool {
+    match Netplay::local_player() {
+      Some(player) => player == self,
+      None => true,
+    }
+  }
+}
+
+impl_variants!(
+  /// Every player, in order.
+  Player { P1, P2, P3, P4 }
+);
+
+/// Queries the current state of netplay.
+pub enum Netplay {}
+
+impl Netplay {
+  const NETPLAY: usize = 0x20;
+
+  /// Whether a netplay session is active.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory#netplay)
+  #[inline]
+  pub fn active() -> bool {
+    unsafe { *ptr::<u8>(Self::NETPLAY) & 0b100 != 0 }
+  }
+
+  /// Returns the player on this device, or [`None`] if netplay isn't active.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory#netplay)
+  #[inline]
+  pub fn local_player() -> Option<Player> {
+    let netplay = unsafe { *ptr::<u8>(Self::NETPLAY) };
+
+    match netplay & 0b100 {
+      0 => None,
+      _ => Some(Player::from_index(netplay)),
+    }
+  }
+}
+
 /// Queries the current state of the mouse.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum Mouse {
   /// The LEFT button.
@@ -63,15 +194,15 @@ pub enum Mouse {
 }
 
 impl Mouse {
-  const MOUSE_POSITION: *const [i16; 2] = 0x1a as *const [i16; 2];
-  const MOUSE_BUTTONS: *const u8 = 0x1e as *const u8;
+  const MOUSE_POSITION: usize = 0x1a;
+  const MOUSE_BUTTONS: usize = 0x1e;
 
   /// Whether this button is currently being pressed.
   ///
   /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory/#mouse_buttons)
   #[inline]
   pub fn pressed(self) -> bool {
-    unsafe { *Self::MOUSE_BUTTONS & self as u8 == self as u8 }
+    unsafe { *ptr::<u8>(Self::MOUSE_BUTTONS) & self as u8 == self as u8 }
   }
 
   /// The current X position.
@@ -79,7 +210,7 @@ impl Mouse {
   /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory/#mouse_x)
   #[inline]
   pub fn x() -> i16 {
-    unsafe { (*Self::MOUSE_POSITION)[0] }
+    unsafe { (*ptr::<[i16; 2]>(Self::MOUSE_POSITION))[0] }
   }
 
   /// The current Y position.
@@ -87,12 +218,17 @@ impl Mouse {
   /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory/#mouse_y)
   #[inline]
   pub fn y() -> i16 {
-    unsafe { (*Self::MOUSE_POSITION)[1] }
+    unsafe { (*ptr::<[i16; 2]>(Self::MOUSE_POSITION))[1] }
   }
 }
 
+impl_variants!(
+  /// Every button, from lowest to highest bit.
+  Mouse { Left, Right, Middle }
+);
+
 /// Queries the current state of the palette.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum Palette {
   /// Colour 1.
@@ -106,14 +242,25 @@ pub enum Palette {
 }
 
 impl Palette {
-  const PALETTE: *mut [u32; 4] = 0x04 as *mut [u32; 4];
+  const PALETTE: usize = 0x04;
+
+  /// Converts the two lowest bits into a palette index.
+  #[inline]
+  const fn from_index(index: u8) -> Self {
+    match index & 0b11 {
+      0 => Self::C1,
+      1 => Self::C2,
+      2 => Self::C3,
+      _ => Self::C4,
+    }
+  }
 
   /// Returns the colour from the palette.
   ///
   /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory/#palette)
   #[inline]
   pub fn load(self) -> Color {
-    unsafe { Color::from_u32((*Self::PALETTE)[self as usize]) }
+    unsafe { Color::from_u32((*ptr::<[u32; 4]>(Self::PALETTE))[self as usize]) }
   }
 
   /// Sets the colour in the palette.
@@ -121,12 +268,85 @@ impl Palette {
   /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory/#palette)
   #[inline]
   pub fn store(self, color: Color) {
-    unsafe { (*Self::PALETTE)[self as usize] = color.to_u32() }
+    unsafe { (*ptr::<[u32; 4]>(Self::PALETTE))[self as usize] = color.to_u32() }
+  }
+}
+
+impl_variants!(
+  /// Every palette index, in order.
+  Palette { C1, C2, C3, C4 }
+);
+
+/// All four palette colours at once.
+///
+/// See the [`palette`] module for presets and effects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Palettes([Color; 4]);
+
+impl Palettes {
+  /// Create new palette colours.
+  #[inline]
+  pub const fn new(c1: Color, c2: Color, c3: Color, c4: Color) -> Self {
+    Self([c1, c2, c3, c4])
+  }
+
+  /// Create new palette colours from `u32`s.
+  #[inline]
+  pub const fn from_u32(colors: [u32; 4]) -> Self {
+    Self([
+      Color::from_u32(colors[0]),
+      Color::from_u32(colors[1]),
+      Color::from_u32(colors[2]),
+      Color::from_u32(colors[3]),
+    ])
+  }
+
+  /// Returns every colour, in palette order.
+  #[inline]
+  pub const fn colors(self) -> [Color; 4] {
+    self.0
+  }
+
+  /// Returns the colour for a palette index.
+  #[inline]
+  pub const fn get(self, palette: Palette) -> Color {
+    self.0[palette as usize]
+  }
+
+  /// Returns these colours with the colour for a palette index replaced.
+  #[inline]
+  pub const fn with(mut self, palette: Palette, color: Color) -> Self {
+    self.0[palette as usize] = color;
+    self
+  }
+
+  /// Returns the current palette.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory/#palette)
+  #[inline]
+  pub fn load_all() -> Self {
+    Self::from_u32(unsafe { *ptr::<[u32; 4]>(Palette::PALETTE) })
+  }
+
+  /// Sets the current palette.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory/#palette)
+  #[inline]
+  pub fn store_all(self) {
+    unsafe { *ptr::<[u32; 4]>(Palette::PALETTE) = self.0.map(Color::to_u32) }
+  }
+}
+
+impl Default for Palettes {
+  /// The console's default palette, [`palette::DEFAULT`].
+  #[inline]
+  fn default() -> Self {
+    palette::DEFAULT
   }
 }
 
 /// Represents a 24-bit colour.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 pub struct Color {
   /// The redness.
   pub r: u8,
@@ -145,7 +365,7 @@ impl Color {
 
   /// Converts this colour to a `u32`.
   pub const fn to_u32(self) -> u32 {
-    ((self.r as u32) << 16) | ((self.g as u32) << 8) as u32 | self.b as u32
+    ((self.r as u32) << 16) | ((self.g as u32) << 8) | self.b as u32
   }
 
   /// Converts a `u32` to a colour.
@@ -159,7 +379,7 @@ impl Color {
 }
 
 /// Queries the current state of the draw colours.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum DrawColor {
   /// Colour 1.
@@ -173,7 +393,7 @@ pub enum DrawColor {
 }
 
 impl DrawColor {
-  const DRAW_COLORS: *mut u16 = 0x14 as *mut u16;
+  const DRAW_COLORS: usize = 0x14;
 
   /// Returns the palette index for the draw colour, or [`None`] for
   /// transparent.
@@ -183,7 +403,7 @@ impl DrawColor {
     let offset = 4 * self as u8;
     let mask: u16 = 0b1111 << offset;
 
-    match unsafe { *Self::DRAW_COLORS & mask } >> offset {
+    match unsafe { *ptr::<u16>(Self::DRAW_COLORS) & mask } >> offset {
       0b0000 => None,
       0b0001 => Some(Palette::C1),
       0b0010 => Some(Palette::C2),
@@ -204,9 +424,325 @@ impl DrawColor {
       None => 0,
     };
 
+    let draw_colors = ptr::<u16>(Self::DRAW_COLORS);
+
+    unsafe {
+      *draw_colors ^= *draw_colors & mask;
+      *draw_colors |= index << offset;
+    }
+  }
+}
+
+impl_variants!(
+  /// Every draw colour, in order.
+  DrawColor { C1, C2, C3, C4 }
+);
+
+/// All four draw colours at once.
+///
+/// Useful for changing the draw colours temporarily, see [`with_draw_colors`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DrawColors(u16);
+
+impl DrawColors {
+  /// Create new draw colours from the palette index for each, or [`None`] for
+  /// transparent.
+  #[inline]
+  pub const fn new(
+    c1: Option<Palette>,
+    c2: Option<Palette>,
+    c3: Option<Palette>,
+    c4: Option<Palette>,
+  ) -> Self {
+    const fn nibble(palette: Option<Palette>) -> u16 {
+      match palette {
+        Some(color) => color as u16 + 1,
+        None => 0,
+      }
+    }
+
+    Self(nibble(c1) | nibble(c2) << 4 | nibble(c3) << 8 | nibble(c4) << 12)
+  }
+
+  /// Create new draw colours from the raw register value.
+  #[inline]
+  pub const fn from_bits(bits: u16) -> Self {
+    Self(bits)
+  }
+
+  /// Returns the raw register value.
+  #[inline]
+  pub const fn bits(self) -> u16 {
+    self.0
+  }
+
+  /// Returns the palette index for a draw colour, or [`None`] for
+  /// transparent.
+  #[inline]
+  pub const fn get(self, color: DrawColor) -> Option<Palette> {
+    match (self.0 >> (4 * color as u8)) & 0b1111 {
+      0 => None,
+      index => Some(Palette::from_index(index as u8 - 1)),
+    }
+  }
+
+  /// Returns these draw colours with the palette index for a draw colour
+  /// replaced, or [`None`] for transparent.
+  #[inline]
+  pub const fn with(self, color: DrawColor, palette: Option<Palette>) -> Self {
+    let offset = 4 * color as u8;
+    let index = match palette {
+      Some(color) => color as u16 + 1,
+      None => 0,
+    };
+
+    Self(self.0 & !(0b1111 << offset) | index << offset)
+  }
+
+  /// Returns the current draw colours.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory/#draw_colors)
+  #[inline]
+  pub fn load() -> Self {
+    unsafe { Self(*ptr::<u16>(DrawColor::DRAW_COLORS)) }
+  }
+
+  /// Sets the current draw colours.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory/#draw_colors)
+  #[inline]
+  pub fn store(self) {
+    unsafe { *ptr::<u16>(DrawColor::DRAW_COLORS) = self.0 }
+  }
+
+  /// Sets the current draw colours until the returned guard is dropped, at
+  /// which point the previous ones are restored.
+  #[inline]
+  pub fn scoped(self) -> DrawColorsGuard {
+    let previous = Self::load();
+    self.store();
+    DrawColorsGuard(previous)
+  }
+}
+
+/// Restores the previous draw colours when dropped.
+///
+/// Returned by [`DrawColors::scoped`].
+#[derive(Debug)]
+#[must_use = "the previous draw colours are restored as soon as this is dropped"]
+pub struct DrawColorsGuard(DrawColors);
+
+impl Drop for DrawColorsGuard {
+  #[inline]
+  fn drop(&mut self) {
+    self.0.store();
+  }
+}
+
+/// Calls `f` with `colors` as the current draw colours, restoring the
+/// previous ones afterwards.
+#[inline]
+pub fn with_draw_colors<R>(colors: DrawColors, f: impl FnOnce() -> R) -> R {
+  let _guard = colors.scoped();
+  f()
+}
+
+/// Queries the current state of the system flags.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum System {
+  /// Keeps the framebuffer between frames instead of clearing it.
+  PreserveFramebuffer = 1 << 0,
+  /// Hides the gamepad overlay on mobile devices.
+  HideGamepadOverlay = 1 << 1,
+}
+
+impl System {
+  const SYSTEM_FLAGS: usize = 0x1f;
+
+  /// Whether this flag is currently set.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory#system_flags)
+  #[inline]
+  pub fn load(self) -> bool {
+    unsafe { *ptr::<u8>(Self::SYSTEM_FLAGS) & self as u8 == self as u8 }
+  }
+
+  /// Sets or clears this flag.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory#system_flags)
+  #[inline]
+  pub fn store(self, enabled: bool) {
+    let flags = ptr::<u8>(Self::SYSTEM_FLAGS);
+
     unsafe {
-      *Self::DRAW_COLORS ^= *Self::DRAW_COLORS & mask;
-      *Self::DRAW_COLORS |= index << offset;
+      match enabled {
+        true => *flags |= self as u8,
+        false => *flags &= !(self as u8),
+      }
+    }
+  }
+}
+
+impl_variants!(
+  /// Every flag, from lowest to highest bit.
+  System { PreserveFramebuffer, HideGamepadOverlay }
+);
+
+/// Represents a rectangular area.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rect {
+  /// The left edge.
+  pub x: i32,
+  /// The top edge.
+  pub y: i32,
+  /// The width.
+  pub width: u32,
+  /// The height.
+  pub height: u32,
+}
+
+impl Rect {
+  /// Create a new rectangle from its position and size.
+  #[inline]
+  pub const fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+    Self {
+      x,
+      y,
+      width,
+      height,
+    }
+  }
+}
+
+/// Queries and modifies the framebuffer.
+///
+/// Each byte holds four pixels, with the leftmost pixel in the least
+/// significant bits. Pixels outside of the screen are ignored.
+pub enum Framebuffer {}
+
+impl Framebuffer {
+  const FRAMEBUFFER: usize = 0xa0;
+
+  /// The width of the screen, in pixels.
+  pub const WIDTH: i32 = 160;
+  /// The height of the screen, in pixels.
+  pub const HEIGHT: i32 = 160;
+  /// The number of bytes in each row.
+  pub const ROW_LEN: usize = Self::WIDTH as usize / 4;
+
+  /// The returned reference must not be held across any other access to the
+  /// framebuffer.
+  #[inline]
+  unsafe fn bytes<'a>() -> &'a mut [u8; 6400] {
+    &mut *ptr(Self::FRAMEBUFFER)
+  }
+
+  #[inline]
+  fn contains(x: i32, y: i32) -> bool {
+    (0..Self::WIDTH).contains(&x) && (0..Self::HEIGHT).contains(&y)
+  }
+
+  /// Returns the colour of a pixel, or [`None`] if it is off-screen.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory#framebuffer)
+  #[inline]
+  pub fn get_pixel(x: i32, y: i32) -> Option<Palette> {
+    if !Self::contains(x, y) {
+      return None;
+    }
+
+    let index = (y * Self::WIDTH + x) as usize;
+    let shift = (x & 0b11) * 2;
+    let byte = unsafe { Self::bytes()[index / 4] };
+
+    Some(Palette::from_index((byte >> shift) & 0b11))
+  }
+
+  /// Sets the colour of a pixel.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory#framebuffer)
+  #[inline]
+  pub fn set_pixel(x: i32, y: i32, color: Palette) {
+    if !Self::contains(x, y) {
+      return;
+    }
+
+    let index = (y * Self::WIDTH + x) as usize;
+    let shift = (x & 0b11) * 2;
+
+    unsafe {
+      let byte = &mut Self::bytes()[index / 4];
+      *byte = (*byte & !(0b11 << shift)) | ((color as u8) << shift);
+    }
+  }
+
+  /// Returns the packed bytes of a row, or [`None`] if it is off-screen.
+  ///
+  /// The bytes are copied out rather than borrowed, since drawing anything
+  /// else would change them behind the reference's back.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory#framebuffer)
+  pub fn row(y: i32) -> Option<[u8; Self::ROW_LEN]> {
+    if !(0..Self::HEIGHT).contains(&y) {
+      return None;
+    }
+
+    let start = y as usize * Self::ROW_LEN;
+    let mut row = [0; Self::ROW_LEN];
+    row.copy_from_slice(unsafe { &Self::bytes()[start..][..Self::ROW_LEN] });
+    Some(row)
+  }
+
+  /// Sets the packed bytes of a row.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory#framebuffer)
+  pub fn set_row(y: i32, row: &[u8; Self::ROW_LEN]) {
+    if !(0..Self::HEIGHT).contains(&y) {
+      return;
+    }
+
+    let start = y as usize * Self::ROW_LEN;
+    unsafe { Self::bytes()[start..][..Self::ROW_LEN].copy_from_slice(row) }
+  }
+
+  /// Sets every pixel to the colour.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory#framebuffer)
+  pub fn clear(color: Palette) {
+    unsafe { Self::bytes().fill(color as u8 * 0b0101_0101) }
+  }
+
+  /// Sets every pixel within the rectangle to the colour.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/memory#framebuffer)
+  pub fn fill(x: i32, y: i32, width: u32, height: u32, color: Palette) {
+    let end_x = x.saturating_add_unsigned(width).min(Self::WIDTH);
+    let end_y = y.saturating_add_unsigned(height).min(Self::HEIGHT);
+
+    for y in y.max(0)..end_y {
+      Self::fill_row(x.max(0), end_x, y, color);
+    }
+  }
+
+  /// Sets the pixels from `start_x` up to `end_x` on an on-screen row.
+  fn fill_row(start_x: i32, end_x: i32, y: i32, color: Palette) {
+    let row = y as usize * Self::ROW_LEN;
+    let mut x = start_x;
+
+    // Set whole bytes at a time once aligned.
+    while x < end_x {
+      if x & 0b11 == 0 && end_x - x >= 4 {
+        let len = (end_x - x) as usize / 4;
+        let start = row + x as usize / 4;
+        unsafe {
+          Self::bytes()[start..][..len].fill(color as u8 * 0b0101_0101)
+        };
+        x += len as i32 * 4;
+      } else {
+        Self::set_pixel(x, y, color);
+        x += 1;
+      }
     }
   }
 }
@@ -215,7 +751,14 @@ pub mod w4 {
   //! The built-in [WASM-4 functions].
   //!
   //! [WASM-4 functions]: https://wasm4.org/docs/reference/functions
+  //!
+  //! With the `mock` feature enabled these are provided by [`crate::mock`]
+  //! instead of the console.
 
+  #[cfg(feature = "mock")]
+  pub use crate::mock::w4::*;
+
+  #[cfg(not(feature = "mock"))]
   extern "C" {
     /// Copies pixels in memory into the framebuffer.
     ///
@@ -280,6 +823,15 @@ pub mod w4 {
     /// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#text-str-x-y)
     #[link_name = "textUtf8"]
     pub fn text(string: *const u8, len: i32, x: i32, y: i32);
+    /// Draws UTF-16 text using the built-in system font.
+    ///
+    /// `len` is measured in bytes, not code units.
+    ///
+    /// Uses `DrawColor::C1` for the text and `DrawColor::C2` for the background.
+    ///
+    /// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#text-str-x-y)
+    #[link_name = "textUtf16"]
+    pub fn text_utf16(string: *const u16, len: i32, x: i32, y: i32);
 
     /// Plays a sound.
     ///
@@ -289,7 +841,7 @@ pub mod w4 {
     /// Read bytes from storage.
     ///
     /// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#diskr-destptr-size)
-    pub fn diskr(dest: *const u8, size: i32) -> i32;
+    pub fn diskr(dest: *mut u8, size: i32) -> i32;
     /// Writes bytes to storage.
     ///
     /// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#diskw-srcptr-size)
@@ -300,14 +852,539 @@ pub mod w4 {
     /// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#trace-str)
     #[link_name = "traceUtf8"]
     pub fn trace(text: *const u8, len: usize);
+    /// Writes a message to the debug console from a C-style format string.
+    ///
+    /// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#tracef-fmt-args)
+    pub fn tracef(fmt: *const u8, args: *const u8);
   }
 }
 
+/// The width and height of a character in the system font, in pixels.
+pub const FONT_SIZE: u32 = 8;
+
+/// Draws text with its top-left corner at `x` and `y`.
+///
+/// Uses `DrawColor::C1` for the text and `DrawColor::C2` for the background.
+/// See [`text!`] for formatted text.
+///
+/// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#text-str-x-y)
+#[inline]
+pub fn text<T: AsRef<str>>(text: T, x: i32, y: i32) {
+  let text = text.as_ref();
+  unsafe { w4::text(text.as_ptr(), text.len() as i32, x, y) }
+}
+
+/// Draws UTF-16 text with its top-left corner at `x` and `y`.
+///
+/// Uses `DrawColor::C1` for the text and `DrawColor::C2` for the background.
+///
+/// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#text-str-x-y)
+#[inline]
+pub fn text_utf16(text: &[u16], x: i32, y: i32) {
+  let len = core::mem::size_of_val(text) as i32;
+  unsafe { w4::text_utf16(text.as_ptr(), len, x, y) }
+}
+
+/// Returns the width of text in pixels, which is that of its longest line.
+#[inline]
+pub fn text_width<T: AsRef<str>>(text: T) -> u32 {
+  let lines = text.as_ref().split('\n');
+  lines
+    .map(|line| line.chars().count() as u32)
+    .max()
+    .unwrap_or(0)
+    * FONT_SIZE
+}
+
+/// Returns the height of text in pixels.
+#[inline]
+pub fn text_height<T: AsRef<str>>(text: T) -> u32 {
+  text.as_ref().split('\n').count() as u32 * FONT_SIZE
+}
+
+/// Draws formatted text with its top-left corner at `x` and `y`.
+///
+/// The text is formatted into a 256 byte [`fmt::Buffer`] on the stack, so
+/// anything longer is truncated.
+///
+/// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#text-str-x-y)
+#[macro_export]
+macro_rules! text {
+  ($x:expr, $y:expr, $($arg:tt)*) => {{
+    let mut buf = $crate::fmt::Buffer::<256>::new();
+    let _ = ::core::fmt::Write::write_fmt(
+      &mut buf,
+      ::core::format_args!($($arg)*),
+    );
+    $crate::text(buf.as_str(), $x, $y);
+  }};
+}
+
 /// Debug prints text to the terminal.
 ///
+/// See [`trace!`] for formatted text.
+///
 /// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#trace-str)
 #[inline]
 pub fn trace<T: AsRef<str>>(text: T) {
   let text = text.as_ref();
   unsafe { w4::trace(text.as_ptr(), text.len()) }
 }
+
+/// Debug prints formatted text to the terminal.
+///
+/// The text is formatted into a 256 byte [`fmt::Buffer`] on the stack, so
+/// anything longer is truncated.
+///
+/// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#trace-str)
+#[macro_export]
+macro_rules! trace {
+  ($($arg:tt)*) => {{
+    let mut buf = $crate::fmt::Buffer::<256>::new();
+    let _ = ::core::fmt::Write::write_fmt(
+      &mut buf,
+      ::core::format_args!($($arg)*),
+    );
+    $crate::trace(buf.as_str());
+  }};
+}
+
+/// A game driven by the console.
+///
+/// Use [`main!`] to hook it up to the console.
+pub trait Game {
+  /// Creates the game, called once when the console starts.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#start)
+  fn init() -> Self;
+
+  /// Advances the game by a frame, called 60 times a second.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#update)
+  fn update(&mut self);
+}
+
+/// Exports the console's `start` and `update` callbacks for a [`Game`].
+///
+/// The game is created in `start` and held for the rest of the session.
+///
+/// ```ignore
+/// struct MyGame {
+///   frame: u32,
+/// }
+///
+/// impl tw4f::Game for MyGame {
+///   fn init() -> Self {
+///     Self { frame: 0 }
+///   }
+///
+///   fn update(&mut self) {
+///     self.frame += 1;
+///   }
+/// }
+///
+/// tw4f::main!(MyGame);
+/// ```
+#[macro_export]
+macro_rules! main {
+  ($game:ty) => {
+    const _: () = {
+      static GAME: $crate::__private::State<$game> =
+        $crate::__private::State::new();
+
+      #[no_mangle]
+      extern "C" fn start() {
+        GAME.start();
+      }
+
+      #[no_mangle]
+      extern "C" fn update() {
+        GAME.update();
+      }
+    };
+  };
+}
+
+#[doc(hidden)]
+pub mod __private {
+  //! Used by [`main!`](crate::main), not public API.
+
+  #![allow(clippy::new_without_default)]
+
+  use crate::Game;
+
+  #[cfg(not(feature = "mock"))]
+  pub struct State<T>(core::cell::UnsafeCell<Option<T>>);
+
+  // SAFETY: The console only ever runs on a single thread.
+  #[cfg(not(feature = "mock"))]
+  unsafe impl<T> Sync for State<T> {}
+
+  #[cfg(not(feature = "mock"))]
+  impl<T: Game> State<T> {
+    #[inline]
+    pub const fn new() -> Self {
+      Self(core::cell::UnsafeCell::new(None))
+    }
+
+    #[inline]
+    pub fn start(&self) {
+      unsafe { *self.0.get() = Some(T::init()) }
+    }
+
+    #[inline]
+    pub fn update(&self) {
+      if let Some(game) = unsafe { &mut *self.0.get() } {
+        game.update();
+      }
+    }
+  }
+
+  // Tests may run on many threads, so the state must be locked.
+  #[cfg(feature = "mock")]
+  pub struct State<T>(std::sync::Mutex<Option<T>>);
+
+  #[cfg(feature = "mock")]
+  impl<T: Game> State<T> {
+    #[inline]
+    pub const fn new() -> Self {
+      Self(std::sync::Mutex::new(None))
+    }
+
+    #[inline]
+    pub fn start(&self) {
+      *self.0.lock().unwrap() = Some(T::init());
+    }
+
+    #[inline]
+    pub fn update(&self) {
+      if let Some(game) = &mut *self.0.lock().unwrap() {
+        game.update();
+      }
+    }
+  }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+  use super::*;
+
+  fn pixel(x: i32, y: i32) -> Option<u8> {
+    Framebuffer::get_pixel(x, y).map(|color| color as u8)
+  }
+
+  #[test]
+  fn framebuffer_pixel_packing() {
+    mock::reset();
+
+    for x in 0..8 {
+      Framebuffer::set_pixel(x, 3, Palette::from_index(x as u8));
+    }
+    for x in 0..8 {
+      assert_eq!(pixel(x, 3), Some(x as u8 & 0b11));
+    }
+
+    let row = 0xa0 + 3 * Framebuffer::ROW_LEN;
+    assert_eq!(mock::peek(row), 0b11_10_01_00);
+    assert_eq!(mock::peek(row + 1), 0b11_10_01_00);
+    assert_eq!(mock::peek(row + 2), 0);
+
+    Framebuffer::set_pixel(2, 3, Palette::C1);
+    assert_eq!(mock::peek(row), 0b11_00_01_00);
+  }
+
+  #[test]
+  fn framebuffer_pixel_off_screen() {
+    mock::reset();
+
+    for (x, y) in [(-1, 0), (0, -1), (160, 0), (0, 160)] {
+      Framebuffer::set_pixel(x, y, Palette::C4);
+      assert_eq!(pixel(x, y), None);
+    }
+    assert!(mock::framebuffer().iter().all(|&byte| byte == 0));
+  }
+
+  #[test]
+  fn framebuffer_fill_unaligned() {
+    mock::reset();
+    Framebuffer::fill(3, 1, 7, 2, Palette::C2);
+
+    for y in 0..4 {
+      for x in 0..12 {
+        let inside = (3..10).contains(&x) && (1..3).contains(&y);
+        assert_eq!(pixel(x, y), Some(inside as u8), "({x}, {y})");
+      }
+    }
+  }
+
+  #[test]
+  fn framebuffer_fill_clipped() {
+    mock::reset();
+    Framebuffer::fill(-5, -5, 8, 7, Palette::C3);
+    Framebuffer::fill(150, 158, u32::MAX, u32::MAX, Palette::C4);
+    Framebuffer::fill(i32::MAX, 0, u32::MAX, 1, Palette::C4);
+
+    let drawn = (0..160)
+      .flat_map(|y| (0..160).map(move |x| (x, y)))
+      .filter(|&(x, y)| pixel(x, y) != Some(0))
+      .count();
+    assert_eq!(drawn, 3 * 2 + 10 * 2);
+    assert_eq!(pixel(2, 1), Some(2));
+    assert_eq!(pixel(3, 1), Some(0));
+    assert_eq!(pixel(150, 158), Some(3));
+    assert_eq!(pixel(159, 159), Some(3));
+  }
+
+  #[test]
+  fn framebuffer_clear() {
+    mock::reset();
+    Framebuffer::clear(Palette::C3);
+
+    assert!(mock::framebuffer()
+      .iter()
+      .all(|&byte| byte == 0b10_10_10_10));
+  }
+
+  #[test]
+  fn framebuffer_rows() {
+    mock::reset();
+
+    let mut row = [0; Framebuffer::ROW_LEN];
+    row[0] = 0b00_00_11_00;
+    Framebuffer::set_row(5, &row);
+    Framebuffer::set_row(160, &row);
+
+    assert_eq!(pixel(1, 5), Some(3));
+    assert_eq!(Framebuffer::row(5), Some(row));
+    assert_eq!(Framebuffer::row(-1), None);
+  }
+
+  #[test]
+  fn system_flags() {
+    mock::reset();
+    assert!(!System::PreserveFramebuffer.load());
+    assert!(!System::HideGamepadOverlay.load());
+
+    System::PreserveFramebuffer.store(true);
+    assert_eq!(mock::peek(0x1f), 0b01);
+    System::HideGamepadOverlay.store(true);
+    assert_eq!(mock::peek(0x1f), 0b11);
+    assert!(System::PreserveFramebuffer.load());
+    assert!(System::HideGamepadOverlay.load());
+
+    System::PreserveFramebuffer.store(false);
+    assert_eq!(mock::peek(0x1f), 0b10);
+    assert!(!System::PreserveFramebuffer.load());
+    assert!(System::HideGamepadOverlay.load());
+  }
+
+  #[test]
+  fn netplay_inactive() {
+    mock::reset();
+
+    assert!(!Netplay::active());
+    assert!(Netplay::local_player().is_none());
+    assert!(Player::P1.is_local());
+    assert!(Player::P4.is_local());
+  }
+
+  #[test]
+  fn netplay_local_player() {
+    mock::reset();
+    mock::set_netplay(Some(Player::P3));
+
+    assert!(Netplay::active());
+    assert!(matches!(Netplay::local_player(), Some(Player::P3)));
+    assert!(Player::P3.is_local());
+    assert!(!Player::P1.is_local());
+
+    mock::set_netplay(None);
+    assert!(!Netplay::active());
+    assert!(Player::P1.is_local());
+  }
+
+  /// The first byte of memory after the framebuffer, free for games to use.
+  const COUNTER: usize = 0x19a0;
+
+  struct Counter(u8);
+
+  impl Game for Counter {
+    fn init() -> Self {
+      Self(mock::peek(COUNTER))
+    }
+
+    fn update(&mut self) {
+      self.0 += 1;
+      mock::poke(COUNTER, self.0);
+    }
+  }
+
+  #[test]
+  fn game_state() {
+    mock::reset();
+    let state = __private::State::<Counter>::new();
+
+    state.update();
+    assert_eq!(mock::peek(COUNTER), 0);
+
+    state.start();
+    state.update();
+    state.update();
+    assert_eq!(mock::peek(COUNTER), 2);
+
+    mock::poke(COUNTER, 10);
+    state.start();
+    state.update();
+    assert_eq!(mock::peek(COUNTER), 11);
+  }
+
+  mod entry {
+    crate::main!(super::Counter);
+  }
+
+  #[test]
+  fn game_entry_points() {
+    extern "C" {
+      fn start();
+      fn update();
+    }
+
+    mock::reset();
+    unsafe {
+      start();
+      update();
+      update();
+      update();
+    }
+    assert_eq!(mock::peek(COUNTER), 3);
+  }
+
+  #[test]
+  fn text_size() {
+    assert_eq!(text_width(""), 0);
+    assert_eq!(text_height(""), 8);
+    assert_eq!(text_width("héllo"), 40);
+    assert_eq!(text_width("ab\nabcd\nabc"), 32);
+    assert_eq!(text_height("ab\nabcd\nabc"), 24);
+    assert_eq!(text_height("ab\n"), 16);
+  }
+
+  #[test]
+  fn text_records() {
+    mock::reset();
+    text("hi", 3, 4);
+    text!(5, 6, "{} + {} = {}", 1, 2, 1 + 2);
+
+    let texts = mock::texts();
+    assert_eq!(texts.len(), 2);
+    assert_eq!(
+      (texts[0].text.as_str(), texts[0].x, texts[0].y),
+      ("hi", 3, 4)
+    );
+    assert_eq!(
+      (texts[1].text.as_str(), texts[1].x, texts[1].y),
+      ("1 + 2 = 3", 5, 6)
+    );
+  }
+
+  #[test]
+  fn text_macro_truncates() {
+    mock::reset();
+    let long = "a".repeat(300);
+    text!(0, 0, "{long}");
+    text!(0, 0, "{}{}", "a".repeat(255), 'é');
+
+    let texts = mock::texts();
+    assert_eq!(texts[0].text, "a".repeat(256));
+    assert_eq!(texts[1].text, "a".repeat(255));
+  }
+
+  #[test]
+  fn text_utf16_length() {
+    mock::reset();
+    let units: std::vec::Vec<u16> = "héllo, 世界".encode_utf16().collect();
+    text_utf16(&units, 1, 2);
+
+    let texts = mock::texts();
+    assert_eq!(texts[0].text, "héllo, 世界");
+    assert_eq!((texts[0].x, texts[0].y), (1, 2));
+  }
+
+  #[test]
+  fn draw_colors_bits() {
+    let colors =
+      DrawColors::new(None, Some(Palette::C1), None, Some(Palette::C4));
+    assert_eq!(colors.bits(), 0x4010);
+    assert!(colors.get(DrawColor::C1).is_none());
+    assert_eq!(colors.get(DrawColor::C4).map(|p| p as u8), Some(3));
+
+    let colors = colors
+      .with(DrawColor::C4, None)
+      .with(DrawColor::C3, Some(Palette::C2));
+    assert_eq!(colors.bits(), 0x0210);
+  }
+
+  #[test]
+  fn draw_colors_scoped() {
+    mock::reset();
+
+    {
+      let _guard = DrawColors::from_bits(0x4321).scoped();
+      assert_eq!(DrawColors::load().bits(), 0x4321);
+
+      {
+        let _guard = DrawColors::from_bits(0x0002).scoped();
+        assert_eq!(DrawColors::load().bits(), 0x0002);
+      }
+      assert_eq!(DrawColors::load().bits(), 0x4321);
+    }
+    assert_eq!(DrawColors::load().bits(), 0x1203);
+
+    let bits = with_draw_colors(DrawColors::from_bits(0x0040), || {
+      DrawColors::load().bits()
+    });
+    assert_eq!(bits, 0x0040);
+    assert_eq!(DrawColors::load().bits(), 0x1203);
+  }
+
+  /// Checks that `all` is in discriminant order and that converting from
+  /// every `u8` only succeeds for the discriminants.
+  fn check_variants<T>(all: &[T], discriminant: fn(T) -> u8)
+  where
+    T: Copy + PartialEq + core::fmt::Debug + TryFrom<u8, Error = InvalidValue>,
+  {
+    let discriminants = all.iter().map(|&v| discriminant(v));
+    assert!(discriminants.is_sorted_by(|a, b| a < b), "{all:?}");
+
+    for value in 0..=u8::MAX {
+      match all.iter().find(|&&v| discriminant(v) == value) {
+        Some(&variant) => assert_eq!(T::try_from(value), Ok(variant)),
+        None => assert_eq!(T::try_from(value), Err(InvalidValue(value))),
+      }
+    }
+  }
+
+  #[test]
+  fn variants() {
+    assert_eq!(Gamepad::ALL.map(|v| v as u8), [1, 2, 16, 32, 64, 128]);
+    check_variants(&Gamepad::ALL, |v| v as u8);
+    assert_eq!(Player::ALL.map(|v| v as u8), [0, 1, 2, 3]);
+    check_variants(&Player::ALL, |v| v as u8);
+    assert_eq!(Mouse::ALL.map(|v| v as u8), [1, 2, 4]);
+    check_variants(&Mouse::ALL, |v| v as u8);
+    assert_eq!(Palette::ALL.map(|v| v as u8), [0, 1, 2, 3]);
+    check_variants(&Palette::ALL, |v| v as u8);
+    assert_eq!(DrawColor::ALL.map(|v| v as u8), [0, 1, 2, 3]);
+    check_variants(&DrawColor::ALL, |v| v as u8);
+    assert_eq!(System::ALL.map(|v| v as u8), [1, 2]);
+    check_variants(&System::ALL, |v| v as u8);
+  }
+
+  #[test]
+  fn invalid_value() {
+    assert_eq!(Palette::try_from(4), Err(InvalidValue(4)));
+    assert_eq!(Gamepad::try_from(8), Err(InvalidValue(8)));
+    assert_eq!(System::try_from(0), Err(InvalidValue(0)));
+    assert_eq!(std::format!("{}", InvalidValue(9)), "invalid value: 9");
+  }
+}
@@ -0,0 +1,732 @@
+//! An in-process fake of the [WASM-4] console for native testing.
+//!
+//! Enabled by the `mock` feature, this replaces the console's memory and the
+//! [`w4`](crate::w4) functions so games can be tested with `cargo test`. Each
+//! thread gets its own console, so tests running in parallel never observe
+//! each other's state.
+//!
+//! Drawing functions rasterize into the framebuffer the same way the
+//! reference runtime does, with the exception of [`w4::text`] and
+//! [`w4::text_utf16`] which are only recorded. Sounds, traces and disk access
+//! are recorded too.
+//!
+//! [WASM-4]: https://wasm4.org
+
+use core::cell::{RefCell, UnsafeCell};
+use std::boxed::Box;
+use std::string::String;
+use std::vec::Vec;
+
+use crate::{ptr, Player};
+
+/// The width and height of the screen, in pixels.
+const SCREEN_SIZE: i32 = 160;
+/// The maximum number of bytes that can be stored on disk.
+const DISK_SIZE: usize = 1024;
+
+/// The number of bytes of console memory.
+const MEMORY_SIZE: usize = 0x10000;
+
+const PALETTE: usize = 0x04;
+const DRAW_COLORS: usize = 0x14;
+const GAMEPADS: usize = 0x16;
+const MOUSE_X: usize = 0x1a;
+const MOUSE_Y: usize = 0x1c;
+const MOUSE_BUTTONS: usize = 0x1e;
+const NETPLAY: usize = 0x20;
+const FRAMEBUFFER: usize = 0xa0;
+const FRAMEBUFFER_SIZE: usize = 6400;
+
+#[repr(C, align(4))]
+struct Memory([u8; MEMORY_SIZE]);
+
+#[derive(Default)]
+struct Record {
+  tones: Vec<Tone>,
+  traces: Vec<String>,
+  texts: Vec<Text>,
+  disk: Vec<u8>,
+}
+
+std::thread_local! {
+  static MEMORY: Box<UnsafeCell<Memory>> = {
+    let memory = Box::new(UnsafeCell::new(Memory([0; MEMORY_SIZE])));
+    init(memory.get().cast());
+    memory
+  };
+  static RECORD: RefCell<Record> = RefCell::default();
+}
+
+/// Writes the values the console starts up with.
+fn init(memory: *mut u8) {
+  let palette: [u32; 4] = [0xe0f8cf, 0x86c06c, 0x306850, 0x071821];
+
+  unsafe {
+    *memory.add(PALETTE).cast::<[u32; 4]>() = palette;
+    *memory.add(DRAW_COLORS).cast::<u16>() = 0x1203;
+  }
+}
+
+/// Returns a pointer to the start of this thread's console memory.
+pub(crate) fn memory() -> *mut u8 {
+  MEMORY.with(|memory| memory.get().cast())
+}
+
+/// A recorded call to [`w4::tone`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tone {
+  /// The packed frequency argument.
+  pub frequency: i32,
+  /// The packed duration argument.
+  pub duration: i32,
+  /// The packed volume argument.
+  pub volume: i32,
+  /// The packed flags argument.
+  pub flags: i32,
+}
+
+/// A recorded call to [`w4::text`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Text {
+  /// The text that was drawn.
+  pub text: String,
+  /// The X position.
+  pub x: i32,
+  /// The Y position.
+  pub y: i32,
+}
+
+/// Resets this thread's console back to how it starts up.
+///
+/// The disk is cleared along with everything else.
+pub fn reset() {
+  let memory = memory();
+
+  unsafe { memory.write_bytes(0, MEMORY_SIZE) };
+  init(memory);
+  RECORD.with(|record| *record.borrow_mut() = Record::default());
+}
+
+/// Reads a byte of console memory.
+///
+/// Panics if `addr` is outside of the 64 KiB of memory.
+pub fn peek(addr: usize) -> u8 {
+  assert!(addr < MEMORY_SIZE, "address out of bounds");
+  unsafe { *ptr::<u8>(addr) }
+}
+
+/// Writes a byte of console memory.
+///
+/// Panics if `addr` is outside of the 64 KiB of memory.
+pub fn poke(addr: usize, value: u8) {
+  assert!(addr < MEMORY_SIZE, "address out of bounds");
+  unsafe { *ptr::<u8>(addr) = value }
+}
+
+/// Sets the buttons held by a player.
+///
+/// `buttons` is a bitmask of [`Gamepad`](crate::Gamepad) values.
+pub fn set_gamepad(player: Player, buttons: u8) {
+  poke(GAMEPADS + player as usize, buttons);
+}
+
+/// Sets the position of the mouse and the buttons held.
+///
+/// `buttons` is a bitmask of [`Mouse`](crate::Mouse) values.
+pub fn set_mouse(x: i16, y: i16, buttons: u8) {
+  unsafe {
+    *ptr::<i16>(MOUSE_X) = x;
+    *ptr::<i16>(MOUSE_Y) = y;
+  }
+  poke(MOUSE_BUTTONS, buttons);
+}
+
+/// Starts a netplay session with `player` on this device, or ends it with
+/// [`None`].
+pub fn set_netplay(player: Option<Player>) {
+  match player {
+    Some(player) => poke(NETPLAY, 0b100 | player as u8),
+    None => poke(NETPLAY, 0),
+  }
+}
+
+/// Returns a copy of the framebuffer.
+pub fn framebuffer() -> [u8; FRAMEBUFFER_SIZE] {
+  unsafe { *ptr::<[u8; FRAMEBUFFER_SIZE]>(FRAMEBUFFER) }
+}
+
+/// Returns the palette index of a pixel, or [`None`] if it is off-screen.
+pub fn pixel(x: i32, y: i32) -> Option<u8> {
+  if !(0..SCREEN_SIZE).contains(&x) || !(0..SCREEN_SIZE).contains(&y) {
+    return None;
+  }
+
+  let index = (y * SCREEN_SIZE + x) as usize;
+  let shift = (x & 0b11) * 2;
+  Some((peek(FRAMEBUFFER + index / 4) >> shift) & 0b11)
+}
+
+/// Returns every tone played so far.
+pub fn tones() -> Vec<Tone> {
+  RECORD.with(|record| record.borrow().tones.clone())
+}
+
+/// Returns every message traced so far.
+pub fn traces() -> Vec<String> {
+  RECORD.with(|record| record.borrow().traces.clone())
+}
+
+/// Returns every piece of text drawn so far.
+pub fn texts() -> Vec<Text> {
+  RECORD.with(|record| record.borrow().texts.clone())
+}
+
+/// Returns the bytes currently stored on disk.
+pub fn disk() -> Vec<u8> {
+  RECORD.with(|record| record.borrow().disk.clone())
+}
+
+/// Replaces the bytes stored on disk, truncating them to 1024 bytes.
+pub fn set_disk(bytes: &[u8]) {
+  let bytes = &bytes[..bytes.len().min(DISK_SIZE)];
+  RECORD.with(|record| record.borrow_mut().disk = bytes.to_vec());
+}
+
+/// Returns the palette index for a draw colour nibble, if not transparent.
+fn draw_color(nibble: usize) -> Option<u8> {
+  let draw_colors = unsafe { *ptr::<u16>(DRAW_COLORS) };
+
+  match (draw_colors >> (nibble * 4)) & 0b1111 {
+    0 => None,
+    index => Some((index as u8 - 1) & 0b11),
+  }
+}
+
+fn point(color: u8, x: i32, y: i32) {
+  if !(0..SCREEN_SIZE).contains(&x) || !(0..SCREEN_SIZE).contains(&y) {
+    return;
+  }
+
+  let index = (y * SCREEN_SIZE + x) as usize;
+  let shift = (x & 0b11) * 2;
+  let byte = ptr::<u8>(FRAMEBUFFER + index / 4);
+
+  unsafe { *byte = (*byte & !(0b11 << shift)) | (color << shift) }
+}
+
+/// Draws the pixels from `start_x` up to `end_x` on row `y`.
+fn span(color: u8, start_x: i32, y: i32, end_x: i32) {
+  for x in start_x.max(0)..end_x.min(SCREEN_SIZE) {
+    point(color, x, y);
+  }
+}
+
+pub mod w4 {
+  //! Stand-ins for the [WASM-4 functions](crate::w4).
+  //!
+  //! These keep the same signatures as the real functions so that code using
+  //! them compiles unchanged.
+
+  #![allow(clippy::missing_safety_doc)]
+
+  use core::ffi::CStr;
+  use core::fmt::Write;
+  use core::slice;
+  use std::string::String;
+
+  use super::{
+    draw_color, point, span, Text, Tone, DISK_SIZE, RECORD, SCREEN_SIZE,
+  };
+  use crate::ptr;
+
+  /// Copies pixels in memory into the framebuffer.
+  pub unsafe fn blit(
+    sprite: *const u8,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    flags: i32,
+  ) {
+    blit_sub(sprite, x, y, width, height, 0, 0, width, flags);
+  }
+
+  /// Copies pixels within a subsection of memory into the framebuffer.
+  #[allow(clippy::too_many_arguments)]
+  pub unsafe fn blit_sub(
+    sprite: *const u8,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    src_x: i32,
+    src_y: i32,
+    stride: i32,
+    flags: i32,
+  ) {
+    let colors = *ptr::<u16>(super::DRAW_COLORS);
+    let bpp2 = flags & 0b0001 != 0;
+    let flip_x = flags & 0b0010 != 0;
+    let flip_y = flags & 0b0100 != 0;
+    let rotate = flags & 0b1000 != 0;
+    // Rotating is a transpose followed by a horizontal flip.
+    let flip_x = flip_x ^ rotate;
+
+    let (min_x, min_y, max_x, max_y) = if rotate {
+      (
+        0.max(y) - y,
+        0.max(x) - x,
+        width.min(SCREEN_SIZE - y),
+        height.min(SCREEN_SIZE - x),
+      )
+    } else {
+      (
+        0.max(x) - x,
+        0.max(y) - y,
+        width.min(SCREEN_SIZE - x),
+        height.min(SCREEN_SIZE - y),
+      )
+    };
+
+    for row in min_y..max_y {
+      for col in min_x..max_x {
+        let (dst_x, dst_y) = match rotate {
+          true => (x + row, y + col),
+          false => (x + col, y + row),
+        };
+        let sx = src_x + if flip_x { width - col - 1 } else { col };
+        let sy = src_y + if flip_y { height - row - 1 } else { row };
+        let bit = (sy * stride + sx) as usize;
+
+        let index = if bpp2 {
+          (*sprite.add(bit >> 2) >> (6 - ((bit & 0b11) << 1))) & 0b11
+        } else {
+          (*sprite.add(bit >> 3) >> (7 - (bit & 0b111))) & 0b1
+        };
+
+        match (colors >> (index * 4)) & 0b1111 {
+          0 => {}
+          dc => point((dc as u8 - 1) & 0b11, dst_x, dst_y),
+        }
+      }
+    }
+  }
+
+  /// Draws a line between two points.
+  pub unsafe fn line(x1: i32, y1: i32, x2: i32, y2: i32) {
+    let Some(color) = draw_color(0) else { return };
+    let (mut x, mut y) = (x1, y1);
+    let dx = (x2 - x1).abs();
+    let dy = -(y2 - y1).abs();
+    let sx = if x1 < x2 { 1 } else { -1 };
+    let sy = if y1 < y2 { 1 } else { -1 };
+    let mut err = dx + dy;
+
+    loop {
+      point(color, x, y);
+      if x == x2 && y == y2 {
+        break;
+      }
+
+      let e2 = 2 * err;
+      if e2 >= dy {
+        err += dy;
+        x += sx;
+      }
+      if e2 <= dx {
+        err += dx;
+        y += sy;
+      }
+    }
+  }
+
+  /// Draws a horizontal line.
+  pub unsafe fn hline(x: i32, y: i32, len: i32) {
+    if let Some(color) = draw_color(0) {
+      span(color, x, y, x + len);
+    }
+  }
+
+  /// Draws a vertical line.
+  pub unsafe fn vline(x: i32, y: i32, len: i32) {
+    if let Some(color) = draw_color(0) {
+      for y in y..y + len {
+        point(color, x, y);
+      }
+    }
+  }
+
+  /// Draws an oval.
+  pub unsafe fn oval(x: i32, y: i32, width: i32, height: i32) {
+    let fill = draw_color(0);
+    let stroke = draw_color(1);
+    let plot = |x: i32, y: i32| {
+      if let Some(color) = stroke {
+        point(color, x, y);
+      }
+    };
+
+    let mut a = width - 1;
+    let b = height - 1;
+    let mut b1 = b % 2;
+    let mut north = y + height / 2;
+    let mut west = x;
+    let mut east = x + width - 1;
+    let mut south = north - b1;
+
+    let mut dx = 4 * (1 - a) * b * b;
+    let mut dy = 4 * (b1 + 1) * a * a;
+    let mut err = dx + dy + b1 * a * a;
+    a = 8 * a * a;
+    b1 = 8 * b * b;
+
+    loop {
+      plot(east, north);
+      plot(west, north);
+      plot(west, south);
+      plot(east, south);
+
+      if let Some(color) = fill {
+        if east - west > 1 {
+          span(color, west + 1, north, east);
+          span(color, west + 1, south, east);
+        }
+      }
+
+      let err2 = 2 * err;
+      if err2 <= dy {
+        north += 1;
+        south -= 1;
+        dy += a;
+        err += dy;
+      }
+      if err2 >= dx || err2 > dy {
+        west += 1;
+        east -= 1;
+        dx += b1;
+        err += dx;
+      }
+
+      if west > east {
+        break;
+      }
+    }
+
+    while north - south < height {
+      plot(west - 1, north);
+      plot(east + 1, north);
+      north += 1;
+      plot(west - 1, south);
+      plot(east + 1, south);
+      south -= 1;
+    }
+  }
+
+  /// Draws a rectangle.
+  pub unsafe fn rect(x: i32, y: i32, width: i32, height: i32) {
+    let start_x = x.max(0);
+    let start_y = y.max(0);
+    let end_x = (x + width).min(SCREEN_SIZE);
+    let end_y = (y + height).min(SCREEN_SIZE);
+
+    if let Some(color) = draw_color(0) {
+      for row in start_y..end_y {
+        span(color, start_x, row, end_x);
+      }
+    }
+
+    if let Some(color) = draw_color(1) {
+      for row in start_y..end_y {
+        point(color, x, row);
+        point(color, x + width - 1, row);
+      }
+      span(color, start_x, y, end_x);
+      span(color, start_x, y + height - 1, end_x);
+    }
+  }
+
+  /// Records the text instead of drawing it.
+  pub unsafe fn text(string: *const u8, len: i32, x: i32, y: i32) {
+    let bytes = slice::from_raw_parts(string, len as usize);
+    let text = String::from_utf8_lossy(bytes).into_owned();
+
+    RECORD.with(|record| record.borrow_mut().texts.push(Text { text, x, y }));
+  }
+
+  /// Records the UTF-16 text instead of drawing it.
+  pub unsafe fn text_utf16(string: *const u16, len: i32, x: i32, y: i32) {
+    let units = slice::from_raw_parts(string, len as usize / 2);
+    let text = String::from_utf16_lossy(units);
+
+    RECORD.with(|record| record.borrow_mut().texts.push(Text { text, x, y }));
+  }
+
+  /// Records the tone instead of playing it.
+  pub unsafe fn tone(frequency: i32, duration: i32, volume: i32, flags: i32) {
+    let tone = Tone {
+      frequency,
+      duration,
+      volume,
+      flags,
+    };
+
+    RECORD.with(|record| record.borrow_mut().tones.push(tone));
+  }
+
+  /// Reads bytes from the fake disk.
+  pub unsafe fn diskr(dest: *mut u8, size: i32) -> i32 {
+    RECORD.with(|record| {
+      let disk = &record.borrow().disk;
+      let len = disk.len().min(size.max(0) as usize);
+
+      disk.as_ptr().copy_to_nonoverlapping(dest, len);
+      len as i32
+    })
+  }
+
+  /// Writes bytes to the fake disk.
+  pub unsafe fn diskw(src: *const u8, size: i32) -> i32 {
+    let len = DISK_SIZE.min(size.max(0) as usize);
+    let bytes = slice::from_raw_parts(src, len);
+
+    RECORD.with(|record| record.borrow_mut().disk = bytes.to_vec());
+    len as i32
+  }
+
+  /// Records the message instead of writing it to the debug console.
+  pub unsafe fn trace(text: *const u8, len: usize) {
+    let bytes = slice::from_raw_parts(text, len);
+    let text = String::from_utf8_lossy(bytes).into_owned();
+
+    RECORD.with(|record| record.borrow_mut().traces.push(text));
+  }
+
+  /// Formats the message and records it instead of writing it to the debug
+  /// console.
+  pub unsafe fn tracef(fmt: *const u8, args: *const u8) {
+    let mut args = args;
+    let mut next = |len: usize| {
+      let arg = slice::from_raw_parts(args, len);
+      args = args.add(len);
+      arg
+    };
+
+    let mut text = String::new();
+    let mut bytes = CStr::from_ptr(fmt.cast()).to_bytes().iter();
+
+    while let Some(&byte) = bytes.next() {
+      if byte != b'%' {
+        text.push(byte as char);
+        continue;
+      }
+
+      let _ = match bytes.next() {
+        Some(b'd') => {
+          write!(text, "{}", i32::from_le_bytes(next(4).try_into().unwrap()))
+        }
+        Some(b'x') => {
+          write!(
+            text,
+            "{:x}",
+            i32::from_le_bytes(next(4).try_into().unwrap())
+          )
+        }
+        Some(b'c') => {
+          let char = u32::from_le_bytes(next(4).try_into().unwrap());
+          write!(text, "{}", char::from_u32(char).unwrap_or('?'))
+        }
+        Some(b'f') => {
+          write!(text, "{}", f64::from_le_bytes(next(8).try_into().unwrap()))
+        }
+        Some(b's') => {
+          const SIZE: usize = core::mem::size_of::<usize>();
+          let ptr = usize::from_le_bytes(next(SIZE).try_into().unwrap());
+          let str = CStr::from_ptr(ptr as *const _);
+          write!(text, "{}", str.to_string_lossy())
+        }
+        Some(&other) => write!(text, "{}", other as char),
+        None => Ok(()),
+      };
+    }
+
+    RECORD.with(|record| record.borrow_mut().traces.push(text));
+  }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+  use super::*;
+
+  /// Returns every pixel that isn't palette colour 0.
+  fn drawn() -> Vec<(i32, i32, u8)> {
+    let mut pixels = Vec::new();
+
+    for y in 0..SCREEN_SIZE {
+      for x in 0..SCREEN_SIZE {
+        match pixel(x, y) {
+          Some(0) | None => {}
+          Some(color) => pixels.push((x, y, color)),
+        }
+      }
+    }
+    pixels
+  }
+
+  fn set_draw_colors(draw_colors: u16) {
+    unsafe { *ptr::<u16>(DRAW_COLORS) = draw_colors }
+  }
+
+  #[test]
+  fn blit_sub_flip_x_clipped() {
+    reset();
+    set_draw_colors(0x20);
+
+    // A single set bit in the first column.
+    let sprite = [0b1000_0000];
+    unsafe { w4::blit_sub(sprite.as_ptr(), -4, 0, 8, 1, 0, 0, 8, 0b0010) };
+
+    assert_eq!(drawn(), [(3, 0, 1)]);
+  }
+
+  #[test]
+  fn blit_sub_flip_y_clipped() {
+    reset();
+    set_draw_colors(0x20);
+
+    let sprite = [0b1000_0000, 0, 0, 0];
+    unsafe { w4::blit_sub(sprite.as_ptr(), 0, 158, 8, 4, 0, 0, 8, 0b0100) };
+    assert_eq!(drawn(), []);
+
+    unsafe { w4::blit_sub(sprite.as_ptr(), 0, -2, 8, 4, 0, 0, 8, 0b0100) };
+    assert_eq!(drawn(), [(0, 1, 1)]);
+  }
+
+  #[test]
+  fn blit_sub_rotate_clipped() {
+    reset();
+    set_draw_colors(0x20);
+
+    // Rotating turns the row anticlockwise, so its first column ends up at
+    // the bottom.
+    let sprite = [0b1000_0000];
+    unsafe { w4::blit_sub(sprite.as_ptr(), 10, -4, 8, 1, 0, 0, 8, 0b1000) };
+
+    assert_eq!(drawn(), [(10, 3, 1)]);
+  }
+
+  #[test]
+  fn blit_sub_source_offset() {
+    reset();
+    set_draw_colors(0x4320);
+
+    // Two rows of 2bpp pixels, drawing only the second and third of the
+    // second row.
+    let sprite = [0b00_00_00_00, 0b00_01_10_11];
+    unsafe { w4::blit_sub(sprite.as_ptr(), 0, 0, 2, 1, 1, 1, 4, 0b0001) };
+
+    assert_eq!(drawn(), [(0, 0, 1), (1, 0, 2)]);
+  }
+
+  #[test]
+  fn oval() {
+    reset();
+    set_draw_colors(0x42);
+
+    unsafe { w4::oval(10, 10, 8, 8) };
+
+    for (x, y) in [(10, 13), (17, 13), (13, 10), (13, 17)] {
+      assert_eq!(pixel(x, y), Some(3), "stroke at ({x}, {y})");
+    }
+    assert_eq!(pixel(13, 13), Some(1));
+    for (x, y) in [(10, 10), (17, 10), (10, 17), (17, 17)] {
+      assert_eq!(pixel(x, y), Some(0), "corner at ({x}, {y})");
+    }
+    assert!(drawn()
+      .iter()
+      .all(|&(x, y, _)| (10..18).contains(&x) && (10..18).contains(&y)));
+  }
+
+  #[test]
+  fn rect_outline_clipped() {
+    reset();
+    set_draw_colors(0x42);
+
+    unsafe { w4::rect(-2, -2, 6, 6) };
+
+    assert_eq!(pixel(0, 0), Some(1));
+    assert_eq!(pixel(2, 2), Some(1));
+    assert_eq!(pixel(3, 0), Some(3));
+    assert_eq!(pixel(0, 3), Some(3));
+    assert_eq!(pixel(3, 3), Some(3));
+    assert_eq!(drawn().len(), 16);
+  }
+
+  #[test]
+  fn rect_transparent_fill() {
+    reset();
+    set_draw_colors(0x40);
+
+    unsafe { w4::rect(158, 158, 4, 4) };
+
+    assert_eq!(drawn(), [(158, 158, 3), (159, 158, 3), (158, 159, 3)]);
+  }
+
+  #[test]
+  fn line() {
+    reset();
+
+    unsafe { w4::line(0, 0, 3, 3) };
+    assert_eq!(drawn(), [(0, 0, 2), (1, 1, 2), (2, 2, 2), (3, 3, 2)]);
+
+    reset();
+    unsafe { w4::line(-5, 10, 5, 10) };
+    assert_eq!(drawn().len(), 6);
+    assert!(drawn().iter().all(|&(_, y, _)| y == 10));
+  }
+
+  #[test]
+  fn trace() {
+    reset();
+
+    let message = "hello";
+    unsafe { w4::trace(message.as_ptr(), message.len()) };
+
+    assert_eq!(traces(), ["hello"]);
+  }
+
+  #[test]
+  fn tracef() {
+    reset();
+
+    let mut args = Vec::new();
+    args.extend_from_slice(&5i32.to_le_bytes());
+    args.extend_from_slice(&255i32.to_le_bytes());
+    args.extend_from_slice(&('A' as u32).to_le_bytes());
+    args.extend_from_slice(&1.5f64.to_le_bytes());
+    let format = c"%d, %x, %c, %f, 100%%";
+    unsafe { w4::tracef(format.as_ptr().cast(), args.as_ptr()) };
+
+    assert_eq!(traces(), ["5, ff, A, 1.5, 100%"]);
+  }
+
+  #[test]
+  fn disk_truncated() {
+    reset();
+
+    let bytes = [7; DISK_SIZE + 100];
+    assert_eq!(
+      unsafe { w4::diskw(bytes.as_ptr(), bytes.len() as i32) },
+      1024
+    );
+    assert_eq!(disk().len(), DISK_SIZE);
+
+    let mut read = [0; DISK_SIZE + 100];
+    let len = unsafe { w4::diskr(read.as_mut_ptr(), read.len() as i32) };
+    assert_eq!(len, 1024);
+    assert!(read[..DISK_SIZE].iter().all(|&byte| byte == 7));
+    assert_eq!(read[DISK_SIZE], 0);
+
+    set_disk(&bytes);
+    assert_eq!(disk().len(), DISK_SIZE);
+  }
+}
@@ -0,0 +1,616 @@
+//! Background music played through [`w4::tone`](crate::w4::tone).
+//!
+//! A [`Song`] is a list of patterns, each a list of rows with a [`Cell`] for
+//! every channel, and an order in which to play them. A [`Sequencer`] steps
+//! through the song once per frame, playing each note with one of the song's
+//! instruments.
+//!
+//! ```ignore
+//! const LEAD: Tone = Tone::new(0).sustain(6).release(4).volume(50);
+//! const BASS: Tone = Tone::new(0).sustain(12).volume(70);
+//!
+//! const A: &[Row] = &[
+//!   [Cell::note(60, 0), Cell::EMPTY, Cell::note(36, 1), Cell::EMPTY],
+//!   [Cell::note(64, 0), Cell::EMPTY, Cell::EMPTY, Cell::EMPTY],
+//!   [Cell::note(67, 0), Cell::EMPTY, Cell::note(43, 1), Cell::EMPTY],
+//!   [Cell::OFF, Cell::EMPTY, Cell::EMPTY, Cell::EMPTY],
+//! ];
+//!
+//! const SONG: Song = Song::new(&[A], &[0, 0], &[LEAD, BASS], 8);
+//!
+//! let mut music = Sequencer::new(SONG);
+//! ```
+
+use crate::note::Note;
+use crate::tone::{Channel, Tone, SILENCE};
+
+/// What happens on a channel when a row is reached.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Cell {
+  /// Whatever is playing carries on.
+  #[default]
+  Empty,
+  /// Whatever is playing is cut off.
+  Off,
+  /// Plays a note with one of the song's instruments.
+  Note {
+    /// The MIDI note number, where 60 is middle C.
+    note: u8,
+    /// The index of the instrument.
+    instrument: u8,
+  },
+}
+
+impl Cell {
+  /// Whatever is playing carries on.
+  pub const EMPTY: Self = Self::Empty;
+  /// Whatever is playing is cut off.
+  pub const OFF: Self = Self::Off;
+
+  /// Create a new cell playing a MIDI note number with an instrument.
+  #[inline]
+  pub const fn note(note: u8, instrument: u8) -> Self {
+    Self::Note { note, instrument }
+  }
+}
+
+/// A cell for each channel, in [`Channel`] order.
+pub type Row = [Cell; 4];
+
+/// Patterns, the order they're played in, and the instruments they use.
+///
+/// Instruments are [`Tone`]s whose frequency and channel are replaced by the
+/// note and the column it's in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Song<'a> {
+  patterns: &'a [&'a [Row]],
+  order: &'a [u8],
+  instruments: &'a [Tone],
+  speed: u8,
+}
+
+impl<'a> Song<'a> {
+  /// Create a new song that plays `patterns` in `order`, advancing a row
+  /// every `speed` frames.
+  ///
+  /// Panics if there's nothing to play, the speed is 0, or an index or note
+  /// is out of range.
+  pub const fn new(
+    patterns: &'a [&'a [Row]],
+    order: &'a [u8],
+    instruments: &'a [Tone],
+    speed: u8,
+  ) -> Self {
+    assert!(!order.is_empty(), "song has no order");
+    assert!(speed != 0, "song has a speed of 0");
+
+    let mut i = 0;
+    while i < order.len() {
+      assert!((order[i] as usize) < patterns.len(), "pattern out of range");
+      assert!(!patterns[order[i] as usize].is_empty(), "pattern is empty");
+      i += 1;
+    }
+
+    let mut i = 0;
+    while i < patterns.len() {
+      let mut row = 0;
+      while row < patterns[i].len() {
+        let mut channel = 0;
+        while channel < 4 {
+          if let Cell::Note { note, instrument } = patterns[i][row][channel] {
+            assert!(note <= Note::MAX.midi(), "note out of range");
+            assert!(
+              (instrument as usize) < instruments.len(),
+              "instrument out of range"
+            );
+          }
+          channel += 1;
+        }
+        row += 1;
+      }
+      i += 1;
+    }
+
+    Self {
+      patterns,
+      order,
+      instruments,
+      speed,
+    }
+  }
+
+  /// The number of frames each row is held for.
+  #[inline]
+  pub const fn speed(&self) -> u8 {
+    self.speed
+  }
+
+  /// The number of entries in the order.
+  #[inline]
+  pub const fn len(&self) -> usize {
+    self.order.len()
+  }
+
+  /// Always `false`, a song can't be created without an order.
+  #[inline]
+  pub const fn is_empty(&self) -> bool {
+    self.order.is_empty()
+  }
+}
+
+/// Plays a [`Song`], advancing once per update.
+///
+/// Sound effects can take a channel away from the music for a while, see
+/// [`Sequencer::play_sfx`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sequencer<'a> {
+  song: Song<'a>,
+  position: usize,
+  row: usize,
+  elapsed: u8,
+  playing: bool,
+  looping: bool,
+  finished: bool,
+  muted: [bool; 4],
+  stolen: [Option<(u8, u32)>; 4],
+}
+
+impl<'a> Sequencer<'a> {
+  /// Create a new sequencer, ready to play the start of a looping song.
+  #[inline]
+  pub const fn new(song: Song<'a>) -> Self {
+    Self {
+      song,
+      position: 0,
+      row: 0,
+      elapsed: 0,
+      playing: true,
+      looping: true,
+      finished: false,
+      muted: [false; 4],
+      stolen: [None; 4],
+    }
+  }
+
+  /// The song being played.
+  #[inline]
+  pub const fn song(&self) -> Song<'a> {
+    self.song
+  }
+
+  /// The current entry in the order and row within its pattern.
+  #[inline]
+  pub const fn position(&self) -> (usize, usize) {
+    (self.position, self.row)
+  }
+
+  /// Goes back to the start of the song.
+  pub fn restart(&mut self) {
+    self.position = 0;
+    self.row = 0;
+    self.elapsed = 0;
+    self.playing = true;
+    self.finished = false;
+    self.silence();
+  }
+
+  /// Stops advancing and cuts off the music.
+  pub fn pause(&mut self) {
+    if self.playing {
+      self.playing = false;
+      self.silence();
+    }
+  }
+
+  /// Continues advancing from the next row.
+  #[inline]
+  pub fn resume(&mut self) {
+    self.playing = !self.finished;
+  }
+
+  /// Whether the song is advancing.
+  #[inline]
+  pub const fn is_playing(&self) -> bool {
+    self.playing
+  }
+
+  /// Sets whether the song starts again after the end of its order.
+  #[inline]
+  pub fn set_looping(&mut self, looping: bool) {
+    self.looping = looping;
+  }
+
+  /// Whether the song starts again after the end of its order.
+  #[inline]
+  pub const fn is_looping(&self) -> bool {
+    self.looping
+  }
+
+  /// Whether a song that isn't looping has played its last row.
+  #[inline]
+  pub const fn is_finished(&self) -> bool {
+    self.finished
+  }
+
+  /// Sets whether the music is kept off a channel.
+  pub fn set_muted(&mut self, channel: Channel, muted: bool) {
+    let index = channel as usize;
+
+    if muted && !self.muted[index] && self.stolen[index].is_none() {
+      SILENCE.channel(channel).play();
+    }
+    self.muted[index] = muted;
+  }
+
+  /// Whether the music is kept off a channel.
+  #[inline]
+  pub const fn is_muted(&self, channel: Channel) -> bool {
+    self.muted[channel as usize]
+  }
+
+  /// Keeps the music off a channel for `frames` updates so that it can be
+  /// used for something else.
+  ///
+  /// Returns `false` if the channel has already been taken with a higher
+  /// priority.
+  pub fn steal(&mut self, channel: Channel, frames: u32, priority: u8) -> bool {
+    let stolen = &mut self.stolen[channel as usize];
+
+    match *stolen {
+      Some((current, _)) if current > priority => false,
+      _ => {
+        *stolen = match frames {
+          0 => None,
+          _ => Some((priority, frames)),
+        };
+        true
+      }
+    }
+  }
+
+  /// Plays a sound effect, keeping the music off its channel until it ends.
+  ///
+  /// Returns `false` without playing anything if the channel has already
+  /// been taken with a higher priority.
+  pub fn play_sfx(&mut self, tone: Tone, priority: u8) -> bool {
+    let played = self.steal(tone.channel, tone.duration(), priority);
+    if played {
+      tone.play();
+    }
+    played
+  }
+
+  /// Gives a stolen channel back to the music, which picks up again from
+  /// the next note.
+  #[inline]
+  pub fn release(&mut self, channel: Channel) {
+    self.stolen[channel as usize] = None;
+  }
+
+  /// Whether the music is being kept off a channel by [`Sequencer::steal`].
+  #[inline]
+  pub const fn is_stolen(&self, channel: Channel) -> bool {
+    self.stolen[channel as usize].is_some()
+  }
+
+  /// Advances the song by an update, which should happen once per frame.
+  pub fn update(&mut self) {
+    if self.playing && self.elapsed == 0 {
+      self.play_row();
+    }
+
+    for stolen in &mut self.stolen {
+      if let Some((_, frames)) = stolen {
+        *frames -= 1;
+        if *frames == 0 {
+          *stolen = None;
+        }
+      }
+    }
+
+    if !self.playing {
+      return;
+    }
+
+    self.elapsed += 1;
+    if self.elapsed < self.song.speed {
+      return;
+    }
+    self.elapsed = 0;
+
+    self.row += 1;
+    if self.row < self.pattern().len() {
+      return;
+    }
+    self.row = 0;
+
+    self.position += 1;
+    if self.position < self.song.order.len() {
+      return;
+    }
+
+    match self.looping {
+      true => self.position = 0,
+      false => {
+        self.position = self.song.order.len() - 1;
+        self.row = self.pattern().len() - 1;
+        self.playing = false;
+        self.finished = true;
+      }
+    }
+  }
+
+  #[inline]
+  fn pattern(&self) -> &'a [Row] {
+    self.song.patterns[self.song.order[self.position] as usize]
+  }
+
+  fn play_row(&self) {
+    let row = self.pattern()[self.row];
+
+    for (index, channel) in Channel::ALL.into_iter().enumerate() {
+      if self.muted[index] || self.stolen[index].is_some() {
+        continue;
+      }
+
+      match row[index] {
+        Cell::Empty => {}
+        Cell::Off => SILENCE.channel(channel).play(),
+        Cell::Note { note, instrument } => {
+          // `Song::new` has already checked that the note is in range.
+          self.song.instruments[instrument as usize]
+            .frequency(Note(note).frequency())
+            .channel(channel)
+            .play();
+        }
+      }
+    }
+  }
+
+  fn silence(&self) {
+    for (index, channel) in Channel::ALL.into_iter().enumerate() {
+      if self.stolen[index].is_none() {
+        SILENCE.channel(channel).play();
+      }
+    }
+  }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+  use std::vec::Vec;
+
+  use super::*;
+  use crate::mock;
+
+  const LEAD: Tone = Tone::new(0).sustain(4).volume(50);
+
+  const A: &[Row] = &[
+    [
+      Cell::note(60, 0),
+      Cell::note(48, 0),
+      Cell::EMPTY,
+      Cell::EMPTY,
+    ],
+    [Cell::note(62, 0), Cell::EMPTY, Cell::EMPTY, Cell::EMPTY],
+    [Cell::OFF, Cell::EMPTY, Cell::EMPTY, Cell::EMPTY],
+  ];
+  const B: &[Row] = &[[Cell::note(69, 0), Cell::EMPTY, Cell::EMPTY, Cell::OFF]];
+
+  /// Plays A then B, a row a frame.
+  const SONG: Song = Song::new(&[A, B], &[0, 1], &[LEAD], 1);
+
+  /// Returns how a note is recorded when played on a channel.
+  fn note(note: u8, channel: Channel) -> mock::Tone {
+    recorded(LEAD.frequency(Note(note).frequency()).channel(channel))
+  }
+
+  /// Returns how silencing a channel is recorded.
+  fn silence(channel: Channel) -> mock::Tone {
+    recorded(SILENCE.channel(channel))
+  }
+
+  /// Returns how a tone is recorded when played.
+  fn recorded(tone: Tone) -> mock::Tone {
+    let [frequency, duration, volume, flags] = tone.encode();
+    mock::Tone {
+      frequency: frequency as i32,
+      duration: duration as i32,
+      volume: volume as i32,
+      flags: flags as i32,
+    }
+  }
+
+  /// Returns the tones played by `f`.
+  fn played(f: impl FnOnce()) -> Vec<mock::Tone> {
+    let start = mock::tones().len();
+    f();
+    mock::tones()[start..].to_vec()
+  }
+
+  #[test]
+  #[should_panic = "note out of range"]
+  fn new_note_out_of_range() {
+    let pattern: &[Row] =
+      &[[Cell::note(128, 0), Cell::EMPTY, Cell::EMPTY, Cell::EMPTY]];
+    Song::new(&[pattern], &[0], &[Tone::new(0)], 1);
+  }
+
+  #[test]
+  fn new_highest_note() {
+    let pattern: &[Row] =
+      &[[Cell::note(127, 0), Cell::EMPTY, Cell::EMPTY, Cell::EMPTY]];
+    Song::new(&[pattern], &[0], &[Tone::new(0)], 1);
+  }
+
+  #[test]
+  fn row_held_for_speed() {
+    mock::reset();
+    let mut music = Sequencer::new(Song::new(&[A], &[0], &[LEAD], 3));
+
+    assert_eq!(
+      played(|| music.update()),
+      [note(60, Channel::Pulse1), note(48, Channel::Pulse2)]
+    );
+    assert_eq!(played(|| music.update()), []);
+    assert_eq!(played(|| music.update()), []);
+    assert_eq!(music.position(), (0, 1));
+    assert_eq!(played(|| music.update()), [note(62, Channel::Pulse1)]);
+  }
+
+  #[test]
+  fn off_silences() {
+    mock::reset();
+    let mut music = Sequencer::new(SONG);
+
+    music.update();
+    music.update();
+    assert_eq!(played(|| music.update()), [silence(Channel::Pulse1)]);
+    assert_eq!(
+      played(|| music.update()),
+      [note(69, Channel::Pulse1), silence(Channel::Noise)]
+    );
+  }
+
+  #[test]
+  fn pause_resume_restart() {
+    mock::reset();
+    let mut music = Sequencer::new(SONG);
+    music.update();
+
+    let all = Channel::ALL.map(silence);
+    assert_eq!(played(|| music.pause()), all);
+    assert!(!music.is_playing());
+    assert_eq!(played(|| music.pause()), []);
+    assert_eq!(played(|| music.update()), []);
+    assert_eq!(music.position(), (0, 1));
+
+    music.resume();
+    assert!(music.is_playing());
+    assert_eq!(played(|| music.update()), [note(62, Channel::Pulse1)]);
+
+    assert_eq!(played(|| music.restart()), all);
+    assert_eq!(music.position(), (0, 0));
+    assert_eq!(
+      played(|| music.update()),
+      [note(60, Channel::Pulse1), note(48, Channel::Pulse2)]
+    );
+  }
+
+  #[test]
+  fn loops() {
+    mock::reset();
+    let mut music = Sequencer::new(SONG);
+
+    for _ in 0..4 {
+      music.update();
+    }
+    assert_eq!(music.position(), (0, 0));
+    assert!(!music.is_finished());
+    assert_eq!(
+      played(|| music.update()),
+      [note(60, Channel::Pulse1), note(48, Channel::Pulse2)]
+    );
+  }
+
+  #[test]
+  fn finishes_without_looping() {
+    mock::reset();
+    let mut music = Sequencer::new(SONG);
+    music.set_looping(false);
+
+    for _ in 0..3 {
+      music.update();
+      assert!(!music.is_finished());
+    }
+    assert_eq!(
+      played(|| music.update()),
+      [note(69, Channel::Pulse1), silence(Channel::Noise)]
+    );
+    assert!(music.is_finished());
+    assert!(!music.is_playing());
+    assert_eq!(music.position(), (1, 0));
+
+    music.resume();
+    assert!(!music.is_playing());
+    assert_eq!(played(|| music.update()), []);
+    assert_eq!(music.position(), (1, 0));
+  }
+
+  #[test]
+  fn muted() {
+    mock::reset();
+    let mut music = Sequencer::new(SONG);
+
+    assert_eq!(
+      played(|| music.set_muted(Channel::Pulse1, true)),
+      [silence(Channel::Pulse1)]
+    );
+    assert_eq!(played(|| music.set_muted(Channel::Pulse1, true)), []);
+    assert!(music.is_muted(Channel::Pulse1));
+    assert_eq!(played(|| music.update()), [note(48, Channel::Pulse2)]);
+
+    music.set_muted(Channel::Pulse1, false);
+    assert_eq!(played(|| music.update()), [note(62, Channel::Pulse1)]);
+  }
+
+  #[test]
+  fn steal_priority() {
+    mock::reset();
+    let mut music = Sequencer::new(SONG);
+
+    assert!(music.steal(Channel::Pulse1, 2, 5));
+    assert!(!music.steal(Channel::Pulse1, 9, 4));
+    assert!(music.steal(Channel::Pulse1, 2, 5));
+    assert!(music.is_stolen(Channel::Pulse1));
+
+    // The music stays off the channel for both updates.
+    assert_eq!(played(|| music.update()), [note(48, Channel::Pulse2)]);
+    assert!(music.is_stolen(Channel::Pulse1));
+    assert_eq!(played(|| music.update()), []);
+    assert!(!music.is_stolen(Channel::Pulse1));
+    assert_eq!(played(|| music.update()), [silence(Channel::Pulse1)]);
+
+    assert!(music.steal(Channel::Pulse1, 9, 1));
+    music.release(Channel::Pulse1);
+    assert!(!music.is_stolen(Channel::Pulse1));
+    assert!(music.steal(Channel::Pulse1, 0, 0));
+    assert!(!music.is_stolen(Channel::Pulse1));
+  }
+
+  #[test]
+  fn stolen_channel_not_silenced() {
+    mock::reset();
+    let mut music = Sequencer::new(SONG);
+    music.steal(Channel::Noise, 10, 0);
+
+    assert_eq!(
+      played(|| music.pause()),
+      Channel::ALL[..3]
+        .iter()
+        .copied()
+        .map(silence)
+        .collect::<Vec<_>>()
+    );
+    assert_eq!(played(|| music.set_muted(Channel::Noise, true)), []);
+  }
+
+  #[test]
+  fn play_sfx() {
+    mock::reset();
+    let mut music = Sequencer::new(SONG);
+    let sfx = Tone::new(880)
+      .sustain(1)
+      .release(1)
+      .channel(Channel::Pulse2);
+
+    assert_eq!(played(|| assert!(music.play_sfx(sfx, 3))), [recorded(sfx)]);
+    assert_eq!(played(|| assert!(!music.play_sfx(sfx, 2))), []);
+
+    assert_eq!(played(|| music.update()), [note(60, Channel::Pulse1)]);
+    assert!(music.is_stolen(Channel::Pulse2));
+    music.update();
+    assert!(!music.is_stolen(Channel::Pulse2));
+    assert!(music.play_sfx(sfx, 0));
+  }
+}
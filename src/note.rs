@@ -0,0 +1,347 @@
+//! Musical notes and their frequencies.
+//!
+//! ```ignore
+//! const A4: Note = match Note::parse("A4") {
+//!   Some(note) => note,
+//!   None => panic!("invalid note"),
+//! };
+//!
+//! Tone::new(A4.frequency()).play();
+//! Tone::from_note(A4.transpose(12).unwrap()).play();
+//! ```
+
+use core::fmt;
+use core::str::FromStr;
+
+/// A note within an octave.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum PitchClass {
+  /// C.
+  #[default]
+  C = 0,
+  /// C♯ or D♭.
+  CSharp,
+  /// D.
+  D,
+  /// D♯ or E♭.
+  DSharp,
+  /// E.
+  E,
+  /// F.
+  F,
+  /// F♯ or G♭.
+  FSharp,
+  /// G.
+  G,
+  /// G♯ or A♭.
+  GSharp,
+  /// A.
+  A,
+  /// A♯ or B♭.
+  ASharp,
+  /// B.
+  B,
+}
+
+impl_variants!(
+  /// Every pitch class, from C to B.
+  PitchClass { C, CSharp, D, DSharp, E, F, FSharp, G, GSharp, A, ASharp, B }
+);
+
+impl PitchClass {
+  /// The name of the pitch class, using sharps.
+  pub const fn name(self) -> &'static str {
+    match self {
+      Self::C => "C",
+      Self::CSharp => "C#",
+      Self::D => "D",
+      Self::DSharp => "D#",
+      Self::E => "E",
+      Self::F => "F",
+      Self::FSharp => "F#",
+      Self::G => "G",
+      Self::GSharp => "G#",
+      Self::A => "A",
+      Self::ASharp => "A#",
+      Self::B => "B",
+    }
+  }
+}
+
+impl fmt::Display for PitchClass {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(self.name())
+  }
+}
+
+/// A note in twelve-tone equal temperament, stored as its MIDI note number.
+///
+/// Octaves range from -1 to 9, so the lowest note is C-1 and the highest is
+/// G9.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Note(pub(crate) u8);
+
+/// The frequency in Hz of every note from C10 to B10, which are shifted down
+/// for lower octaves.
+const OCTAVE: [u16; 12] = [
+  16744, 17740, 18795, 19912, 21096, 22351, 23680, 25088, 26580, 28160, 29834,
+  31609,
+];
+
+impl Note {
+  /// The lowest note, C-1.
+  pub const MIN: Self = Self(0);
+  /// The highest note, G9.
+  pub const MAX: Self = Self(127);
+  /// Middle C, C4.
+  pub const MIDDLE_C: Self = Self(60);
+  /// A4, tuned to 440 Hz.
+  pub const A4: Self = Self(69);
+
+  /// Create a new note from its pitch class and octave, or [`None`] if it's
+  /// out of range.
+  #[inline]
+  pub const fn new(pitch_class: PitchClass, octave: i8) -> Option<Self> {
+    match (octave as i16 + 1) * 12 + pitch_class as i16 {
+      midi @ 0..=127 => Some(Self(midi as u8)),
+      _ => None,
+    }
+  }
+
+  /// Create a new note from its MIDI note number, or [`None`] if it's above
+  /// 127.
+  #[inline]
+  pub const fn from_midi(midi: u8) -> Option<Self> {
+    match midi {
+      0..=127 => Some(Self(midi)),
+      _ => None,
+    }
+  }
+
+  /// Parses a note name such as `"C4"`, `"F#3"` or `"Bb-1"`.
+  ///
+  /// The letter may be either case and can be followed by a `#` for sharp
+  /// or a `b` for flat, then the octave.
+  pub const fn parse(name: &str) -> Option<Self> {
+    let bytes = name.as_bytes();
+    if bytes.is_empty() {
+      return None;
+    }
+
+    let pitch_class = match bytes[0].to_ascii_uppercase() {
+      b'C' => 0,
+      b'D' => 2,
+      b'E' => 4,
+      b'F' => 5,
+      b'G' => 7,
+      b'A' => 9,
+      b'B' => 11,
+      _ => return None,
+    };
+
+    let mut i = 1;
+    let accidental = match bytes.len() > i {
+      true if bytes[i] == b'#' => 1,
+      true if bytes[i] == b'b' => -1,
+      _ => 0,
+    };
+    if accidental != 0 {
+      i += 1;
+    }
+
+    let octave = match (bytes.len() - i, bytes.len()) {
+      (1, len) if bytes[len - 1].is_ascii_digit() => {
+        (bytes[len - 1] - b'0') as i16
+      }
+      (2, len) if bytes[len - 2] == b'-' && bytes[len - 1] == b'1' => -1,
+      _ => return None,
+    };
+
+    match (octave + 1) * 12 + pitch_class + accidental {
+      midi @ 0..=127 => Some(Self(midi as u8)),
+      _ => None,
+    }
+  }
+
+  /// The MIDI note number, where 60 is middle C.
+  #[inline]
+  pub const fn midi(self) -> u8 {
+    self.0
+  }
+
+  /// The note within its octave.
+  #[inline]
+  pub const fn pitch_class(self) -> PitchClass {
+    PitchClass::ALL[(self.0 % 12) as usize]
+  }
+
+  /// The octave, from -1 to 9.
+  #[inline]
+  pub const fn octave(self) -> i8 {
+    (self.0 / 12) as i8 - 1
+  }
+
+  /// The frequency in Hz, rounded to the nearest whole number.
+  #[inline]
+  pub const fn frequency(self) -> u16 {
+    let shift = 11 - (self.0 / 12) as u32;
+    let hz = OCTAVE[(self.0 % 12) as usize] as u32;
+    ((hz + (1 << (shift - 1))) >> shift) as u16
+  }
+
+  /// Moves the note up or down by `semitones`, or [`None`] if it goes out of
+  /// range.
+  #[inline]
+  pub const fn transpose(self, semitones: i8) -> Option<Self> {
+    match self.0 as i16 + semitones as i16 {
+      midi @ 0..=127 => Some(Self(midi as u8)),
+      _ => None,
+    }
+  }
+}
+
+impl Default for Note {
+  /// Middle C.
+  #[inline]
+  fn default() -> Self {
+    Self::MIDDLE_C
+  }
+}
+
+impl fmt::Display for Note {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}{}", self.pitch_class(), self.octave())
+  }
+}
+
+/// The error returned when parsing an invalid note name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ParseNoteError;
+
+impl fmt::Display for ParseNoteError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("invalid note name")
+  }
+}
+
+impl core::error::Error for ParseNoteError {}
+
+impl FromStr for Note {
+  type Err = ParseNoteError;
+
+  #[inline]
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    Self::parse(name).ok_or(ParseNoteError)
+  }
+}
+
+impl TryFrom<u8> for Note {
+  type Error = crate::InvalidValue;
+
+  #[inline]
+  fn try_from(midi: u8) -> Result<Self, Self::Error> {
+    Self::from_midi(midi).ok_or(crate::InvalidValue(midi))
+  }
+}
+
+impl From<Note> for u8 {
+  #[inline]
+  fn from(note: Note) -> Self {
+    note.midi()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use core::fmt::Write;
+
+  fn midi(name: &str) -> Option<u8> {
+    Note::parse(name).map(Note::midi)
+  }
+
+  #[test]
+  fn parse() {
+    assert_eq!(midi("C4"), Some(60));
+    assert_eq!(midi("c#4"), Some(61));
+    assert_eq!(midi("Bb-1"), Some(10));
+    assert_eq!(midi("C-1"), Some(0));
+    assert_eq!(midi("G9"), Some(127));
+
+    assert_eq!(midi("Cb-1"), None);
+    assert_eq!(midi("G#9"), None);
+    assert_eq!(midi("A9"), None);
+    assert_eq!(midi("B#9"), None);
+    assert_eq!(midi(""), None);
+    assert_eq!(midi("C"), None);
+    assert_eq!(midi("C#"), None);
+    assert_eq!(midi("C-"), None);
+    assert_eq!(midi("C10"), None);
+    assert_eq!(midi("C-2"), None);
+    assert_eq!(midi("H4"), None);
+    assert_eq!(midi("Cx4"), None);
+    assert_eq!(midi("C4 "), None);
+
+    assert_eq!("F#3".parse(), Ok(Note(54)));
+    assert_eq!("H4".parse::<Note>(), Err(ParseNoteError));
+  }
+
+  #[test]
+  fn frequency() {
+    assert_eq!(Note::A4.frequency(), 440);
+    assert_eq!(Note::MIDDLE_C.frequency(), 262);
+    assert_eq!(Note::MIN.frequency(), 8);
+    assert_eq!(Note::MAX.frequency(), 12544);
+  }
+
+  #[test]
+  fn new() {
+    assert_eq!(Note::new(PitchClass::C, -1), Some(Note::MIN));
+    assert_eq!(Note::new(PitchClass::C, 4), Some(Note::MIDDLE_C));
+    assert_eq!(Note::new(PitchClass::G, 9), Some(Note::MAX));
+
+    assert_eq!(Note::new(PitchClass::GSharp, 9), None);
+    assert_eq!(Note::new(PitchClass::B, -2), None);
+    assert_eq!(Note::new(PitchClass::C, i8::MIN), None);
+    assert_eq!(Note::new(PitchClass::C, i8::MAX), None);
+  }
+
+  #[test]
+  fn transpose() {
+    assert_eq!(Note::A4.transpose(12), Some(Note(81)));
+    assert_eq!(Note::A4.transpose(-9), Some(Note::MIDDLE_C));
+
+    assert_eq!(Note::MIN.transpose(0), Some(Note::MIN));
+    assert_eq!(Note::MIN.transpose(-1), None);
+    assert_eq!(Note::MIN.transpose(i8::MAX), Some(Note::MAX));
+    assert_eq!(Note::MAX.transpose(1), None);
+    assert_eq!(Note::MAX.transpose(-127), Some(Note::MIN));
+    assert_eq!(Note::MAX.transpose(i8::MIN), None);
+  }
+
+  #[test]
+  fn display_round_trip() {
+    for midi in 0..=127 {
+      let note = Note(midi);
+      let mut name = crate::fmt::Buffer::<8>::new();
+      write!(name, "{note}").unwrap();
+
+      assert_eq!(name.as_str().parse(), Ok(note));
+      assert_eq!(note.pitch_class() as u8, midi % 12);
+    }
+
+    let mut name = crate::fmt::Buffer::<8>::new();
+    write!(name, "{}", Note(10)).unwrap();
+    assert_eq!(name.as_str(), "A#-1");
+  }
+
+  #[test]
+  fn try_from_u8() {
+    assert_eq!(Note::try_from(0), Ok(Note::MIN));
+    assert_eq!(Note::try_from(127), Ok(Note::MAX));
+    assert_eq!(Note::try_from(128), Err(crate::InvalidValue(128)));
+    assert_eq!(Note::try_from(u8::MAX), Err(crate::InvalidValue(u8::MAX)));
+    assert_eq!(u8::from(Note::A4), 69);
+  }
+}
@@ -0,0 +1,385 @@
+//! Palette presets and effects built on top of [`Palettes`].
+//!
+//! Presets are ordered from lightest to darkest, like the default palette.
+//! Effects are advanced with `update` once per frame, which also stores the
+//! resulting palette.
+
+use crate::{Color, Palette, Palettes};
+
+/// The palette the console starts up with.
+pub const DEFAULT: Palettes =
+  Palettes::from_u32([0xe0f8cf, 0x86c06c, 0x306850, 0x071821]);
+/// The green shades of the original Game Boy.
+pub const GAME_BOY: Palettes =
+  Palettes::from_u32([0x9bbc0f, 0x8bac0f, 0x306230, 0x0f380f]);
+/// The grey shades of the Game Boy Pocket.
+pub const GAME_BOY_POCKET: Palettes =
+  Palettes::from_u32([0xc4cfa1, 0x8b956d, 0x4d533c, 0x1f1f1f]);
+/// Evenly spaced greys from white to black.
+pub const GRAYSCALE: Palettes =
+  Palettes::from_u32([0xffffff, 0xaaaaaa, 0x555555, 0x000000]);
+/// "Ice Cream GB" by Kerrie Lake.
+pub const ICE_CREAM: Palettes =
+  Palettes::from_u32([0xfff6d3, 0xf9a875, 0xeb6b6f, 0x7c3f58]);
+/// "Kirokaze Gameboy" by Kirokaze.
+pub const KIROKAZE: Palettes =
+  Palettes::from_u32([0xe2f3e4, 0x94e344, 0x46878f, 0x332c50]);
+/// "Mist GB" by Kerrie Lake.
+pub const MIST: Palettes =
+  Palettes::from_u32([0xc4f0c2, 0x5ab9a8, 0x1e606e, 0x2d1b00]);
+/// "EN4" by Endesga.
+pub const EN4: Palettes =
+  Palettes::from_u32([0xfbf7f3, 0xe5b083, 0x426e5d, 0x20283d]);
+/// "Curiosities" by sukinapan.
+pub const CURIOSITIES: Palettes =
+  Palettes::from_u32([0xffeecc, 0x00b9be, 0x15788c, 0x46425e]);
+/// "Spacehaze" by WildLeoKnight.
+pub const SPACEHAZE: Palettes =
+  Palettes::from_u32([0xf8e3c4, 0xcc3495, 0x6b1fb1, 0x0b0630]);
+
+/// Blends between two colours, with `t` out of `max` of the way to `to`.
+///
+/// Anything from `max` onwards is `to`, even when `max` is zero.
+#[inline]
+fn blend(from: Color, to: Color, t: u16, max: u16) -> Color {
+  if t >= max {
+    return to;
+  }
+
+  from.lerp(to, (t as u32 * 255 / max as u32) as u8)
+}
+
+/// Gradually changes the palette from one set of colours to another.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fade {
+  from: Palettes,
+  to: Palettes,
+  frames: u16,
+  elapsed: u16,
+}
+
+impl Fade {
+  /// Create a new fade taking `frames` updates.
+  #[inline]
+  pub const fn new(from: Palettes, to: Palettes, frames: u16) -> Self {
+    Self {
+      from,
+      to,
+      frames,
+      elapsed: 0,
+    }
+  }
+
+  /// Create a new fade to black taking `frames` updates.
+  #[inline]
+  pub const fn to_black(from: Palettes, frames: u16) -> Self {
+    let black = Color::rgb(0, 0, 0);
+    Self::new(from, Palettes::new(black, black, black, black), frames)
+  }
+
+  /// Create a new fade to white taking `frames` updates.
+  #[inline]
+  pub const fn to_white(from: Palettes, frames: u16) -> Self {
+    let white = Color::rgb(255, 255, 255);
+    Self::new(from, Palettes::new(white, white, white, white), frames)
+  }
+
+  /// Returns the colours for the current update.
+  pub fn current(&self) -> Palettes {
+    let [c1, c2, c3, c4] = [Palette::C1, Palette::C2, Palette::C3, Palette::C4]
+      .map(|p| {
+        blend(self.from.get(p), self.to.get(p), self.elapsed, self.frames)
+      });
+
+    Palettes::new(c1, c2, c3, c4)
+  }
+
+  /// Whether the fade has reached its final colours.
+  #[inline]
+  pub fn is_finished(&self) -> bool {
+    self.elapsed >= self.frames
+  }
+
+  /// Advances the fade by an update and stores the colours.
+  pub fn update(&mut self) {
+    self.elapsed = self.elapsed.saturating_add(1).min(self.frames);
+    self.current().store_all();
+  }
+}
+
+/// Briefly replaces every colour with a single one, then restores them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Flash {
+  base: Palettes,
+  color: Color,
+  frames: u16,
+  elapsed: u16,
+}
+
+impl Flash {
+  /// Create a new flash to `color` lasting `frames` updates.
+  #[inline]
+  pub const fn new(base: Palettes, color: Color, frames: u16) -> Self {
+    Self {
+      base,
+      color,
+      frames,
+      elapsed: 0,
+    }
+  }
+
+  /// Whether the flash is over and the colours have been restored.
+  #[inline]
+  pub fn is_finished(&self) -> bool {
+    self.elapsed > self.frames
+  }
+
+  /// Advances the flash by an update and stores the colours.
+  pub fn update(&mut self) {
+    self.elapsed = self.elapsed.saturating_add(1);
+
+    match self.elapsed <= self.frames {
+      true => Palettes::new(self.color, self.color, self.color, self.color),
+      false => self.base,
+    }
+    .store_all();
+  }
+}
+
+/// Rotates a range of colours through the palette, such as for flowing
+/// water.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cycle {
+  base: Palettes,
+  first: Palette,
+  last: Palette,
+  period: u16,
+  elapsed: u16,
+  offset: u8,
+}
+
+impl Cycle {
+  /// Create a new cycle that rotates the colours from `first` to `last`
+  /// inclusive by one every `period` updates.
+  #[inline]
+  pub const fn new(
+    base: Palettes,
+    first: Palette,
+    last: Palette,
+    period: u16,
+  ) -> Self {
+    Self {
+      base,
+      first,
+      last,
+      period,
+      elapsed: 0,
+      offset: 0,
+    }
+  }
+
+  /// Returns the colours for the current update.
+  pub fn current(&self) -> Palettes {
+    let (first, last) = (self.first as u8, self.last as u8);
+    if first >= last {
+      return self.base;
+    }
+
+    let len = last - first + 1;
+    let mut colors = self.base;
+    for i in 0..len {
+      let from = Palette::from_index(first + (i + self.offset) % len);
+      colors = colors.with(Palette::from_index(first + i), self.base.get(from));
+    }
+
+    colors
+  }
+
+  /// Advances the cycle by an update and stores the colours.
+  pub fn update(&mut self) {
+    self.elapsed += 1;
+    if self.elapsed >= self.period.max(1) {
+      self.elapsed = 0;
+      let len = (self.last as u8).saturating_sub(self.first as u8) + 1;
+      self.offset = (self.offset + 1) % len;
+    }
+
+    self.current().store_all();
+  }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+  use super::*;
+  use crate::mock;
+
+  /// Returns the stored palette as `u32`s.
+  fn stored() -> [u32; 4] {
+    Palettes::load_all().colors().map(Color::to_u32)
+  }
+
+  /// Returns the greyscale preset as `u32`s.
+  fn grayscale() -> [u32; 4] {
+    GRAYSCALE.colors().map(Color::to_u32)
+  }
+
+  #[test]
+  fn load_store() {
+    mock::reset();
+    assert_eq!(stored(), DEFAULT.colors().map(Color::to_u32));
+
+    GAME_BOY.store_all();
+    assert_eq!(stored(), [0x9bbc0f, 0x8bac0f, 0x306230, 0x0f380f]);
+    assert_eq!(
+      [mock::peek(0x04), mock::peek(0x05), mock::peek(0x06)],
+      [0x0f, 0xbc, 0x9b]
+    );
+
+    let white = Color::rgb(255, 255, 255);
+    GAME_BOY.with(Palette::C3, white).store_all();
+    assert_eq!(Palettes::load_all().get(Palette::C3).to_u32(), 0xffffff);
+    assert_eq!(Palettes::load_all().get(Palette::C4).to_u32(), 0x0f380f);
+  }
+
+  #[test]
+  fn presets_light_to_dark() {
+    let presets = [
+      DEFAULT,
+      GAME_BOY,
+      GAME_BOY_POCKET,
+      GRAYSCALE,
+      ICE_CREAM,
+      KIROKAZE,
+      MIST,
+      EN4,
+      CURIOSITIES,
+      SPACEHAZE,
+    ];
+
+    for preset in presets {
+      let luma = preset.colors().map(|color| {
+        299 * color.r as u32 + 587 * color.g as u32 + 114 * color.b as u32
+      });
+      assert!(luma.is_sorted_by(|a, b| a > b), "{luma:?}");
+    }
+  }
+
+  #[test]
+  fn fade_steps() {
+    mock::reset();
+    let mut fade = Fade::to_white(GRAYSCALE, 4);
+    assert_eq!(fade.current().colors()[3].to_u32(), 0x000000);
+
+    let mut steps = [0; 4];
+    for step in &mut steps {
+      fade.update();
+      *step = stored()[3];
+    }
+    assert_eq!(steps, [0x3f3f3f, 0x7f7f7f, 0xbfbfbf, 0xffffff]);
+    assert!(fade.is_finished());
+
+    fade.update();
+    assert_eq!(stored(), [0xffffff; 4]);
+  }
+
+  #[test]
+  fn fade_zero_frames() {
+    mock::reset();
+    let mut fade = Fade::new(DEFAULT, GRAYSCALE, 0);
+    assert!(fade.is_finished());
+    assert_eq!(fade.current().colors().map(Color::to_u32), grayscale());
+
+    fade.update();
+    assert_eq!(stored(), grayscale());
+  }
+
+  #[test]
+  fn fade_longest() {
+    mock::reset();
+    let mut fade = Fade::to_black(DEFAULT, u16::MAX);
+
+    for _ in 0..=u16::MAX {
+      fade.update();
+    }
+    assert!(fade.is_finished());
+  }
+
+  #[test]
+  fn fade_to_black() {
+    mock::reset();
+    let mut fade = Fade::to_black(DEFAULT, 10);
+
+    for _ in 0..10 {
+      fade.update();
+    }
+    assert!(fade.is_finished());
+    assert_eq!(stored(), [0; 4]);
+  }
+
+  #[test]
+  fn flash() {
+    mock::reset();
+    let mut flash = Flash::new(DEFAULT, Color::rgb(255, 0, 0), 2);
+
+    for _ in 0..2 {
+      flash.update();
+      assert_eq!(stored(), [0xff0000; 4]);
+      assert!(!flash.is_finished());
+    }
+
+    flash.update();
+    assert_eq!(stored(), DEFAULT.colors().map(Color::to_u32));
+    assert!(flash.is_finished());
+  }
+
+  #[test]
+  fn flash_zero_frames() {
+    mock::reset();
+    let mut flash = Flash::new(GRAYSCALE, Color::rgb(255, 0, 0), 0);
+
+    flash.update();
+    assert_eq!(stored(), grayscale());
+    assert!(flash.is_finished());
+  }
+
+  #[test]
+  fn cycle_wraps() {
+    mock::reset();
+    let mut cycle = Cycle::new(GRAYSCALE, Palette::C2, Palette::C4, 2);
+    let [white, light, dark, black] = grayscale();
+
+    let mut stores = [[0; 4]; 6];
+    for store in &mut stores {
+      cycle.update();
+      *store = stored();
+    }
+
+    assert_eq!(
+      stores,
+      [
+        [white, light, dark, black],
+        [white, dark, black, light],
+        [white, dark, black, light],
+        [white, black, light, dark],
+        [white, black, light, dark],
+        [white, light, dark, black],
+      ]
+    );
+  }
+
+  #[test]
+  fn cycle_empty_range() {
+    mock::reset();
+
+    for (first, last) in
+      [(Palette::C3, Palette::C3), (Palette::C4, Palette::C1)]
+    {
+      let mut cycle = Cycle::new(GRAYSCALE, first, last, 0);
+
+      for _ in 0..5 {
+        cycle.update();
+        assert_eq!(stored(), grayscale());
+      }
+    }
+  }
+}
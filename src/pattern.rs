@@ -0,0 +1,357 @@
+//! Patterned fills drawn directly into the [`Framebuffer`].
+//!
+//! With only four colours, dithering two of them together is the usual way
+//! to fake shading. Patterns are anchored to the screen rather than the shape,
+//! so neighbouring shapes line up seamlessly.
+
+use crate::shape::{circle_span, polygon_spans};
+use crate::{Framebuffer, Palette};
+
+/// The 4x4 Bayer matrix used by [`Pattern::bayer`].
+const BAYER: [[u8; 4]; 4] =
+  [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// An 8x8 mask repeated across the screen.
+///
+/// Each byte is a row, with the leftmost pixel in the most significant bit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Pattern([u8; 8]);
+
+impl Pattern {
+  /// Every pixel is set.
+  pub const SOLID: Self = Self([0xff; 8]);
+  /// No pixel is set.
+  pub const EMPTY: Self = Self([0x00; 8]);
+  /// Alternating pixels are set.
+  pub const CHECKER: Self =
+    Self([0xaa, 0x55, 0xaa, 0x55, 0xaa, 0x55, 0xaa, 0x55]);
+  /// Alternating rows are set.
+  pub const HORIZONTAL: Self =
+    Self([0xff, 0x00, 0xff, 0x00, 0xff, 0x00, 0xff, 0x00]);
+  /// Alternating columns are set.
+  pub const VERTICAL: Self = Self([0xaa; 8]);
+  /// Diagonal lines rising to the right.
+  pub const DIAGONAL: Self =
+    Self([0x11, 0x22, 0x44, 0x88, 0x11, 0x22, 0x44, 0x88]);
+
+  /// Create a new pattern from its rows.
+  #[inline]
+  pub const fn new(rows: [u8; 8]) -> Self {
+    Self(rows)
+  }
+
+  /// Create an ordered dither pattern with `intensity` out of 16 pixels set.
+  ///
+  /// Anything above 16 is treated as 16.
+  pub const fn bayer(intensity: u8) -> Self {
+    let mut rows = [0; 8];
+
+    let mut y = 0;
+    while y < 8 {
+      let mut x = 0;
+      while x < 8 {
+        if BAYER[y % 4][x % 4] < intensity {
+          rows[y] |= 0x80 >> x;
+        }
+        x += 1;
+      }
+      y += 1;
+    }
+
+    Self(rows)
+  }
+
+  /// Returns the rows of the pattern.
+  #[inline]
+  pub const fn rows(self) -> [u8; 8] {
+    self.0
+  }
+
+  /// Returns the pattern with every pixel flipped.
+  #[inline]
+  pub const fn invert(self) -> Self {
+    let mut rows = self.0;
+
+    let mut y = 0;
+    while y < 8 {
+      rows[y] = !rows[y];
+      y += 1;
+    }
+
+    Self(rows)
+  }
+
+  /// Whether the pixel at `x` and `y` on the screen is set.
+  #[inline]
+  pub const fn contains(self, x: i32, y: i32) -> bool {
+    self.0[(y & 0b111) as usize] & (0x80 >> (x & 0b111)) != 0
+  }
+}
+
+/// A pattern along with the colours it is drawn in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Paint {
+  /// The pattern.
+  pub pattern: Pattern,
+  /// The colour of the set pixels.
+  pub on: Palette,
+  /// The colour of the unset pixels, or [`None`] for transparent.
+  pub off: Option<Palette>,
+}
+
+impl Paint {
+  /// Create a new paint.
+  #[inline]
+  pub const fn new(
+    pattern: Pattern,
+    on: Palette,
+    off: Option<Palette>,
+  ) -> Self {
+    Self { pattern, on, off }
+  }
+
+  /// Create a paint that dithers from `from` to `to` as `intensity` rises
+  /// from 0 to 16.
+  #[inline]
+  pub const fn dither(from: Palette, to: Palette, intensity: u8) -> Self {
+    Self::new(Pattern::bayer(intensity), to, Some(from))
+  }
+
+  /// Returns the colour of the pixel at `x` and `y` on the screen, or
+  /// [`None`] for transparent.
+  #[inline]
+  pub const fn color(self, x: i32, y: i32) -> Option<Palette> {
+    match self.pattern.contains(x, y) {
+      true => Some(self.on),
+      false => self.off,
+    }
+  }
+
+  /// Paints the pixels from `start_x` to `end_x` inclusive on row `y`.
+  fn span(self, y: i32, start_x: i32, end_x: i32) {
+    if !(0..Framebuffer::HEIGHT).contains(&y) {
+      return;
+    }
+
+    for x in start_x.max(0)..=end_x.min(Framebuffer::WIDTH - 1) {
+      if let Some(color) = self.color(x, y) {
+        Framebuffer::set_pixel(x, y, color);
+      }
+    }
+  }
+}
+
+/// Fills a rectangle with the paint.
+pub fn rect(x: i32, y: i32, width: u32, height: u32, paint: Paint) {
+  if width == 0 {
+    return;
+  }
+
+  let end_x = x.saturating_add_unsigned(width - 1);
+  let end_y = y.saturating_add_unsigned(height).min(Framebuffer::HEIGHT);
+
+  for row in y.max(0)..end_y {
+    paint.span(row, x, end_x);
+  }
+}
+
+/// Fills an oval within the rectangle with the paint.
+pub fn oval(x: i32, y: i32, width: u32, height: u32, paint: Paint) {
+  let end_y = y.saturating_add_unsigned(height).min(Framebuffer::HEIGHT);
+
+  for row in y.max(0)..end_y {
+    if let Some((start_x, end_x)) = oval_span(x, y, width, height, row) {
+      paint.span(row, start_x, end_x);
+    }
+  }
+}
+
+/// Fills a circle centered on `x` and `y` with the paint.
+pub fn circle(x: i32, y: i32, radius: u32, paint: Paint) {
+  let radius = radius.min(i32::MAX as u32) as i32;
+  let end_y = y.saturating_add(radius).min(Framebuffer::HEIGHT - 1);
+
+  for row in y.saturating_sub(radius).max(0)..=end_y {
+    if let Some((start_x, end_x)) = circle_span(x, y, radius, row) {
+      paint.span(row, start_x, end_x);
+    }
+  }
+}
+
+/// Fills a polygon between the points with the paint, using the even-odd
+/// rule.
+///
+/// Panics if there are more than [`MAX_POINTS`](crate::shape::MAX_POINTS)
+/// points.
+pub fn polygon(points: &[(i32, i32)], paint: Paint) {
+  polygon_spans(points, |y, start_x, end_x| paint.span(y, start_x, end_x));
+}
+
+/// Returns the inclusive span of row `row` within an oval.
+fn oval_span(
+  x: i32,
+  y: i32,
+  width: u32,
+  height: u32,
+  row: i32,
+) -> Option<(i32, i32)> {
+  if width == 0 || !(y..y.saturating_add_unsigned(height)).contains(&row) {
+    return None;
+  }
+
+  // Measured in half pixels from the center.
+  let (width, height) = (width as i128, height as i128);
+  let center_x = 2 * x as i128 + width - 1;
+  let center_y = 2 * y as i128 + height - 1;
+  let dy = 2 * row as i128 - center_y;
+  // Unsigned since the numerator only just fits.
+  let rest = (height * height - dy * dy).max(0) as u128;
+  let half = ((width * width) as u128 * rest / (height * height) as u128)
+    .isqrt() as i128;
+
+  let start_x = (center_x - half + 1).div_euclid(2);
+  let end_x = (center_x + half).div_euclid(2);
+  Some((
+    start_x.max(i32::MIN as i128) as i32,
+    end_x.min(i32::MAX as i128) as i32,
+  ))
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+  use super::*;
+  use crate::mock;
+
+  const CHECKER: Paint = Paint::new(Pattern::CHECKER, Palette::C4, None);
+  const SOLID: Paint = Paint::new(Pattern::SOLID, Palette::C2, None);
+
+  /// Returns the top-left corner of the screen, with `.` for palette 0, `o`
+  /// for 1, `x` for 2 and `#` for 3.
+  fn drawn(width: i32, height: i32) -> std::vec::Vec<std::string::String> {
+    (0..height)
+      .map(|y| {
+        (0..width)
+          .map(|x| match mock::pixel(x, y) {
+            Some(0) => '.',
+            Some(1) => 'o',
+            Some(2) => 'x',
+            _ => '#',
+          })
+          .collect()
+      })
+      .collect()
+  }
+
+  #[test]
+  fn bayer() {
+    assert_eq!(Pattern::bayer(0).rows(), Pattern::EMPTY.rows());
+    assert_eq!(Pattern::bayer(1).rows(), [0x88, 0, 0, 0, 0x88, 0, 0, 0]);
+    assert_eq!(Pattern::bayer(8).rows(), Pattern::CHECKER.rows());
+    assert_eq!(Pattern::bayer(16).rows(), Pattern::SOLID.rows());
+    assert_eq!(Pattern::bayer(u8::MAX).rows(), Pattern::SOLID.rows());
+
+    for intensity in 0..=16 {
+      let rows = Pattern::bayer(intensity).rows();
+      let set: u32 = rows.iter().map(|row| row.count_ones()).sum();
+      assert_eq!(set, 4 * intensity as u32);
+    }
+  }
+
+  #[test]
+  fn rect_anchored_to_screen() {
+    mock::reset();
+    rect(1, 1, 5, 3, CHECKER);
+
+    assert_eq!(
+      drawn(8, 5),
+      ["........", ".#.#.#..", "..#.#...", ".#.#.#..", "........"]
+    );
+  }
+
+  #[test]
+  fn rect_clipped() {
+    mock::reset();
+    let paint = Paint::new(Pattern::CHECKER, Palette::C4, Some(Palette::C3));
+    rect(-3, -2, 5, 4, paint);
+
+    assert_eq!(drawn(4, 3), ["#x..", "x#..", "...."]);
+
+    rect(150, 150, u32::MAX, u32::MAX, SOLID);
+    assert_eq!(mock::pixel(149, 149), Some(0));
+    assert_eq!(mock::pixel(150, 150), Some(1));
+    assert_eq!(mock::pixel(159, 159), Some(1));
+  }
+
+  #[test]
+  fn rect_empty() {
+    mock::reset();
+    rect(0, 0, 0, 10, SOLID);
+    rect(0, 0, 10, 0, SOLID);
+    rect(i32::MAX, 0, 0, 10, SOLID);
+
+    assert!(mock::framebuffer().iter().all(|&byte| byte == 0));
+  }
+
+  #[test]
+  fn oval_fill() {
+    mock::reset();
+    oval(0, 0, 8, 5, SOLID);
+
+    assert_eq!(
+      drawn(10, 6),
+      [
+        "..oooo....",
+        "oooooooo..",
+        "oooooooo..",
+        "oooooooo..",
+        "..oooo....",
+        "..........",
+      ]
+    );
+  }
+
+  #[test]
+  fn oval_clipped() {
+    mock::reset();
+    oval(-4, -2, 8, 5, SOLID);
+    assert_eq!(drawn(6, 4), ["oooo..", "oooo..", "oo....", "......"]);
+
+    oval(i32::MIN, i32::MIN, u32::MAX, u32::MAX, SOLID);
+    oval(80, 80, 0, 10, CHECKER);
+  }
+
+  #[test]
+  fn circle_fill() {
+    mock::reset();
+    circle(3, 3, 3, CHECKER);
+
+    assert_eq!(
+      drawn(8, 8),
+      [
+        "..#.#...", ".#.#.#..", "#.#.#.#.", ".#.#.#..", "#.#.#.#.", ".#.#.#..",
+        "..#.#...", "........",
+      ]
+    );
+  }
+
+  #[test]
+  fn circle_clipped() {
+    mock::reset();
+    circle(0, 1, 2, SOLID);
+    assert_eq!(drawn(4, 5), ["ooo.", "ooo.", "ooo.", "oo..", "...."]);
+
+    circle(80, 80, u32::MAX, SOLID);
+    assert!(drawn(160, 160).iter().all(|row| !row.contains('.')));
+  }
+
+  #[test]
+  fn polygon_fill() {
+    mock::reset();
+    polygon(&[(0, 0), (6, 0), (6, 3), (0, 3)], CHECKER);
+
+    assert_eq!(
+      drawn(8, 4),
+      ["#.#.#.#.", ".#.#.#..", "#.#.#.#.", "........"]
+    );
+  }
+}
@@ -0,0 +1,513 @@
+//! Sound effects made of several tones played one after another.
+//!
+//! ```ignore
+//! let mut sounds = SfxManager::new();
+//!
+//! if input.just_pressed(Gamepad::X, Player::P1) {
+//!   sounds.play(sfx::JUMP);
+//! }
+//!
+//! sounds.update();
+//! ```
+
+use crate::music::Sequencer;
+use crate::tone::{Channel, DutyCycle, Tone, SILENCE};
+
+/// A single tone of a sound effect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Step {
+  /// The tone to play, whose channel is replaced by the sound effect's.
+  pub tone: Tone,
+  /// The number of updates until the next step, at least 1.
+  pub frames: u8,
+}
+
+impl Step {
+  /// Create a new step.
+  #[inline]
+  pub const fn new(tone: Tone, frames: u8) -> Self {
+    Self { tone, frames }
+  }
+}
+
+/// A sequence of tones played on one channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Sfx<'a> {
+  steps: &'a [Step],
+  channel: Channel,
+  priority: u8,
+}
+
+impl<'a> Sfx<'a> {
+  /// Create a new sound effect on `channel` with a priority of 0.
+  ///
+  /// Panics if there are no steps.
+  #[inline]
+  pub const fn new(steps: &'a [Step], channel: Channel) -> Self {
+    assert!(!steps.is_empty(), "sound effect has no steps");
+
+    Self {
+      steps,
+      channel,
+      priority: 0,
+    }
+  }
+
+  /// Sets the channel the sound effect is played on.
+  #[inline]
+  pub const fn channel(mut self, channel: Channel) -> Self {
+    self.channel = channel;
+    self
+  }
+
+  /// Sets the priority, which decides whether it can interrupt another
+  /// sound effect or the music.
+  #[inline]
+  pub const fn priority(mut self, priority: u8) -> Self {
+    self.priority = priority;
+    self
+  }
+
+  /// The steps, in the order they're played.
+  #[inline]
+  pub const fn steps(&self) -> &'a [Step] {
+    self.steps
+  }
+
+  /// The number of updates from the first step to the end of the last.
+  pub const fn duration(&self) -> u32 {
+    let mut frames = 0;
+
+    let mut i = 0;
+    while i < self.steps.len() {
+      frames += step_frames(&self.steps[i]) as u32;
+      i += 1;
+    }
+
+    frames
+  }
+}
+
+#[inline]
+const fn step_frames(step: &Step) -> u8 {
+  match step.frames {
+    0 => 1,
+    frames => frames,
+  }
+}
+
+/// A short rising sweep.
+pub const JUMP: Sfx<'static> = Sfx::new(
+  &[Step::new(
+    Tone::new(220)
+      .slide(660)
+      .sustain(6)
+      .release(4)
+      .volume(60)
+      .duty_cycle(DutyCycle::Half),
+    10,
+  )],
+  Channel::Pulse2,
+);
+
+/// Two quick rising notes.
+pub const COIN: Sfx<'static> = Sfx::new(
+  &[
+    Step::new(Tone::new(988).sustain(4).volume(50), 4),
+    Step::new(Tone::new(1319).sustain(6).release(10).volume(50), 16),
+  ],
+  Channel::Pulse2,
+);
+
+/// A rising arpeggio.
+pub const POWER_UP: Sfx<'static> = Sfx::new(
+  &[
+    Step::new(Tone::new(523).sustain(4).volume(50), 4),
+    Step::new(Tone::new(659).sustain(4).volume(50), 4),
+    Step::new(Tone::new(784).sustain(4).volume(50), 4),
+    Step::new(Tone::new(1047).sustain(8).release(8).volume(50), 16),
+  ],
+  Channel::Pulse2,
+);
+
+/// A falling sweep.
+pub const LASER: Sfx<'static> = Sfx::new(
+  &[Step::new(
+    Tone::new(1200)
+      .slide(200)
+      .sustain(8)
+      .volume(40)
+      .duty_cycle(DutyCycle::Quarter),
+    8,
+  )],
+  Channel::Pulse2,
+);
+
+/// A single short beep, for menus.
+pub const BLIP: Sfx<'static> = Sfx::new(
+  &[Step::new(Tone::new(880).sustain(3).volume(40), 3)],
+  Channel::Pulse2,
+);
+
+/// A short burst of noise.
+pub const HIT: Sfx<'static> = Sfx::new(
+  &[Step::new(
+    Tone::new(400).slide(100).sustain(2).release(6).volume(80),
+    8,
+  )],
+  Channel::Noise,
+);
+
+/// A long, fading burst of noise.
+pub const EXPLOSION: Sfx<'static> = Sfx::new(
+  &[
+    Step::new(Tone::new(600).slide(200).sustain(4).volume(100), 4),
+    Step::new(
+      Tone::new(200).slide(40).sustain(6).release(30).volume(80),
+      36,
+    ),
+  ],
+  Channel::Noise,
+);
+
+/// The most sound effects that can wait on each channel.
+pub const QUEUE_LEN: usize = 4;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Voice<'a> {
+  current: Option<Sfx<'a>>,
+  step: usize,
+  elapsed: u8,
+  queue: [Option<Sfx<'a>>; QUEUE_LEN],
+}
+
+/// Plays sound effects, one at a time on each channel, advancing once per
+/// update.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SfxManager<'a> {
+  voices: [Voice<'a>; 4],
+}
+
+impl<'a> SfxManager<'a> {
+  /// Create a new manager with nothing playing.
+  #[inline]
+  pub const fn new() -> Self {
+    const IDLE: Voice = Voice {
+      current: None,
+      step: 0,
+      elapsed: 0,
+      queue: [None; QUEUE_LEN],
+    };
+
+    Self { voices: [IDLE; 4] }
+  }
+
+  /// Starts playing a sound effect from the next update, interrupting the
+  /// one on its channel. Anything queued still plays afterwards.
+  ///
+  /// Returns `false` without playing anything if the sound effect on its
+  /// channel has a higher priority.
+  pub fn play(&mut self, sfx: Sfx<'a>) -> bool {
+    let voice = &mut self.voices[sfx.channel as usize];
+
+    match voice.current {
+      Some(current) if current.priority > sfx.priority => false,
+      _ => {
+        voice.current = Some(sfx);
+        voice.step = 0;
+        voice.elapsed = 0;
+        true
+      }
+    }
+  }
+
+  /// Plays a sound effect once everything before it on its channel has
+  /// finished.
+  ///
+  /// Returns `false` if [`QUEUE_LEN`] sound effects are already waiting.
+  pub fn queue(&mut self, sfx: Sfx<'a>) -> bool {
+    let voice = &mut self.voices[sfx.channel as usize];
+
+    if voice.current.is_none() {
+      voice.current = Some(sfx);
+      voice.step = 0;
+      voice.elapsed = 0;
+      return true;
+    }
+
+    match voice.queue.iter_mut().find(|slot| slot.is_none()) {
+      Some(slot) => {
+        *slot = Some(sfx);
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Stops the sound effect on a channel and forgets anything queued.
+  pub fn cancel(&mut self, channel: Channel) {
+    let voice = &mut self.voices[channel as usize];
+
+    if voice.current.take().is_some() {
+      SILENCE.channel(channel).play();
+    }
+    voice.queue = [None; QUEUE_LEN];
+  }
+
+  /// Stops every sound effect and forgets anything queued.
+  pub fn cancel_all(&mut self) {
+    for channel in Channel::ALL {
+      self.cancel(channel);
+    }
+  }
+
+  /// Whether a sound effect is playing on a channel.
+  #[inline]
+  pub fn is_playing(&self, channel: Channel) -> bool {
+    self.voices[channel as usize].current.is_some()
+  }
+
+  /// Advances every sound effect by an update, which should happen once per
+  /// frame.
+  #[inline]
+  pub fn update(&mut self) {
+    self.advance(|_, _| true);
+  }
+
+  /// Advances every sound effect by an update like
+  /// [`SfxManager::update`], keeping the music off their channels.
+  ///
+  /// Steps aren't played on channels that the music has given to something
+  /// with a higher priority, see [`Sequencer::steal`]. This should be called
+  /// before [`Sequencer::update`].
+  #[inline]
+  pub fn update_with(&mut self, music: &mut Sequencer) {
+    self.advance(|channel, priority| music.steal(channel, 1, priority));
+  }
+
+  fn advance(&mut self, mut take: impl FnMut(Channel, u8) -> bool) {
+    for (voice, channel) in self.voices.iter_mut().zip(Channel::ALL) {
+      let Some(sfx) = voice.current else {
+        continue;
+      };
+
+      let step = &sfx.steps[voice.step];
+      if take(channel, sfx.priority) && voice.elapsed == 0 {
+        step.tone.channel(channel).play();
+      }
+
+      voice.elapsed += 1;
+      if voice.elapsed < step_frames(step) {
+        continue;
+      }
+      voice.elapsed = 0;
+
+      voice.step += 1;
+      if voice.step < sfx.steps.len() {
+        continue;
+      }
+      voice.step = 0;
+
+      voice.current = voice.queue[0];
+      voice.queue.copy_within(1.., 0);
+      voice.queue[QUEUE_LEN - 1] = None;
+    }
+  }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+  use std::vec::Vec;
+
+  use super::*;
+  use crate::mock;
+  use crate::music::{Cell, Row, Song};
+
+  const A: Tone = Tone::new(100);
+  const B: Tone = Tone::new(200);
+  const C: Tone = Tone::new(300);
+
+  /// Plays A for two updates, B for one and C for three.
+  const STEPS: Sfx = Sfx::new(
+    &[Step::new(A, 2), Step::new(B, 0), Step::new(C, 3)],
+    Channel::Pulse1,
+  );
+  const SHORT: Sfx = Sfx::new(&[Step::new(B, 1)], Channel::Pulse1);
+
+  const LEAD: Tone = Tone::new(0).volume(10);
+  const PATTERN: &[Row] =
+    &[[Cell::EMPTY, Cell::note(69, 0), Cell::EMPTY, Cell::EMPTY]];
+  /// Plays A4 on the second channel every update.
+  const SONG: Song = Song::new(&[PATTERN], &[0], &[LEAD], 1);
+  const LONG: Sfx = Sfx::new(&[Step::new(A, 2)], Channel::Pulse2).priority(1);
+
+  /// Returns how a tone is recorded when played on a channel.
+  fn recorded(tone: Tone, channel: Channel) -> mock::Tone {
+    let [frequency, duration, volume, flags] = tone.channel(channel).encode();
+    mock::Tone {
+      frequency: frequency as i32,
+      duration: duration as i32,
+      volume: volume as i32,
+      flags: flags as i32,
+    }
+  }
+
+  /// Returns the tones played by `f`.
+  fn played(f: impl FnOnce()) -> Vec<mock::Tone> {
+    let start = mock::tones().len();
+    f();
+    mock::tones()[start..].to_vec()
+  }
+
+  /// Returns the tones played by the sound effects and music in an update.
+  fn frame(sounds: &mut SfxManager, music: &mut Sequencer) -> Vec<mock::Tone> {
+    played(|| {
+      sounds.update_with(music);
+      music.update();
+    })
+  }
+
+  #[test]
+  fn step_timing() {
+    mock::reset();
+    let mut sounds = SfxManager::new();
+    assert_eq!(STEPS.duration(), 6);
+
+    assert!(sounds.play(STEPS));
+    assert_eq!(played(|| sounds.update()), [recorded(A, Channel::Pulse1)]);
+    assert_eq!(played(|| sounds.update()), []);
+    assert_eq!(played(|| sounds.update()), [recorded(B, Channel::Pulse1)]);
+    assert_eq!(played(|| sounds.update()), [recorded(C, Channel::Pulse1)]);
+    assert_eq!(played(|| sounds.update()), []);
+    assert!(sounds.is_playing(Channel::Pulse1));
+    assert_eq!(played(|| sounds.update()), []);
+    assert!(!sounds.is_playing(Channel::Pulse1));
+    assert_eq!(played(|| sounds.update()), []);
+  }
+
+  #[test]
+  fn channel_replaced() {
+    mock::reset();
+    let mut sounds = SfxManager::new();
+
+    sounds.play(SHORT.channel(Channel::Noise));
+    assert!(sounds.is_playing(Channel::Noise));
+    assert!(!sounds.is_playing(Channel::Pulse1));
+    assert_eq!(played(|| sounds.update()), [recorded(B, Channel::Noise)]);
+  }
+
+  #[test]
+  fn play_priority() {
+    mock::reset();
+    let mut sounds = SfxManager::new();
+
+    assert!(sounds.play(STEPS.priority(2)));
+    sounds.update();
+    assert!(!sounds.play(SHORT.priority(1)));
+    assert_eq!(played(|| sounds.update()), []);
+
+    // An equal priority interrupts, starting from its first step.
+    assert!(sounds.play(SHORT.priority(2)));
+    assert_eq!(played(|| sounds.update()), [recorded(B, Channel::Pulse1)]);
+    assert!(!sounds.is_playing(Channel::Pulse1));
+  }
+
+  #[test]
+  fn queue() {
+    mock::reset();
+    let mut sounds = SfxManager::new();
+
+    assert!(sounds.queue(SHORT));
+    for _ in 0..QUEUE_LEN {
+      assert!(sounds.queue(STEPS));
+    }
+    assert!(!sounds.queue(STEPS));
+
+    assert_eq!(played(|| sounds.update()), [recorded(B, Channel::Pulse1)]);
+    assert_eq!(played(|| sounds.update()), [recorded(A, Channel::Pulse1)]);
+
+    let frames = QUEUE_LEN as u32 * STEPS.duration() - 1;
+    let tones = played(|| (0..frames).for_each(|_| sounds.update()));
+    assert_eq!(tones.len(), 3 * QUEUE_LEN - 1);
+    assert!(!sounds.is_playing(Channel::Pulse1));
+  }
+
+  #[test]
+  fn play_keeps_queue() {
+    mock::reset();
+    let mut sounds = SfxManager::new();
+
+    sounds.play(STEPS);
+    sounds.queue(SHORT);
+    sounds.play(SHORT);
+
+    assert_eq!(played(|| sounds.update()), [recorded(B, Channel::Pulse1)]);
+    assert_eq!(played(|| sounds.update()), [recorded(B, Channel::Pulse1)]);
+    assert!(!sounds.is_playing(Channel::Pulse1));
+  }
+
+  #[test]
+  fn cancel() {
+    mock::reset();
+    let mut sounds = SfxManager::new();
+    let silence = recorded(SILENCE, Channel::Pulse1);
+
+    sounds.play(STEPS);
+    sounds.queue(SHORT);
+    sounds.update();
+    assert_eq!(played(|| sounds.cancel(Channel::Pulse1)), [silence]);
+    assert!(!sounds.is_playing(Channel::Pulse1));
+    assert_eq!(played(|| sounds.update()), []);
+    assert_eq!(played(|| sounds.cancel(Channel::Pulse1)), []);
+
+    sounds.play(SHORT.channel(Channel::Noise));
+    sounds.play(SHORT.channel(Channel::Triangle));
+    assert_eq!(
+      played(|| sounds.cancel_all()),
+      [
+        recorded(SILENCE, Channel::Triangle),
+        recorded(SILENCE, Channel::Noise)
+      ]
+    );
+  }
+
+  #[test]
+  fn update_with_music() {
+    mock::reset();
+    let mut music = Sequencer::new(SONG);
+    let mut sounds = SfxManager::new();
+    let note = recorded(LEAD.frequency(440), Channel::Pulse2);
+
+    assert_eq!(frame(&mut sounds, &mut music), [note]);
+
+    // The music is kept off the channel while the sound effect plays.
+    sounds.play(LONG);
+    assert_eq!(
+      frame(&mut sounds, &mut music),
+      [recorded(A, Channel::Pulse2)]
+    );
+    assert_eq!(frame(&mut sounds, &mut music), []);
+    assert!(!sounds.is_playing(Channel::Pulse2));
+    assert!(!music.is_stolen(Channel::Pulse2));
+    assert_eq!(frame(&mut sounds, &mut music), [note]);
+  }
+
+  #[test]
+  fn update_with_higher_priority() {
+    mock::reset();
+    let mut music = Sequencer::new(SONG);
+    let mut sounds = SfxManager::new();
+
+    // Something more important has the channel, so the steps are skipped.
+    music.steal(Channel::Pulse2, 3, 9);
+    sounds.play(LONG);
+    assert_eq!(frame(&mut sounds, &mut music), []);
+    assert_eq!(frame(&mut sounds, &mut music), []);
+    assert!(!sounds.is_playing(Channel::Pulse2));
+    assert_eq!(frame(&mut sounds, &mut music), []);
+    assert_eq!(
+      frame(&mut sounds, &mut music),
+      [recorded(LEAD.frequency(440), Channel::Pulse2)]
+    );
+  }
+}
@@ -0,0 +1,610 @@
+//! Shapes drawn directly into the [`Framebuffer`].
+//!
+//! Like the built-in [`w4::rect`](crate::w4::rect) and
+//! [`w4::oval`](crate::w4::oval), shapes use `DrawColor::C1` for the fill and
+//! `DrawColor::C2` for the outline, while lines only use `DrawColor::C1`. When
+//! the outline is transparent the fill covers the edges instead. Everything is
+//! clipped to the screen.
+
+use crate::{DrawColor, DrawColors, Framebuffer, Palette};
+
+/// The most points [`polygon`] accepts.
+pub const MAX_POINTS: usize = 64;
+
+/// Draws a circle centered on `x` and `y`.
+pub fn circle(x: i32, y: i32, radius: u32) {
+  let radius = radius.min(i32::MAX as u32) as i32;
+  let (fill, stroke) = colors();
+
+  convex(
+    y.saturating_sub(radius),
+    y.saturating_add(radius),
+    |row| circle_span(x, y, radius, row),
+    fill,
+    stroke,
+  );
+}
+
+/// Draws a rectangle with rounded corners.
+///
+/// `radius` is clamped to fit within the rectangle.
+pub fn round_rect(x: i32, y: i32, width: u32, height: u32, radius: u32) {
+  if width == 0 || height == 0 {
+    return;
+  }
+
+  let (fill, stroke) = colors();
+  let bottom = y.saturating_add_unsigned(height - 1);
+  let span = |row| round_rect_span(x, y, width, height, radius, row);
+
+  convex(y, bottom, span, fill, stroke);
+}
+
+/// Draws a triangle between three points.
+pub fn triangle(a: (i32, i32), b: (i32, i32), c: (i32, i32)) {
+  polygon(&[a, b, c]);
+}
+
+/// Draws a polygon between the points, which may be concave or
+/// self-intersecting.
+///
+/// Overlapping areas are filled using the even-odd rule.
+///
+/// Panics if there are more than [`MAX_POINTS`] points.
+pub fn polygon(points: &[(i32, i32)]) {
+  let (fill, stroke) = colors();
+
+  if let Some(color) = fill {
+    polygon_spans(points, |y, start_x, end_x| span(y, start_x, end_x, color));
+  }
+  if let Some(color) = stroke.or(fill) {
+    edges(points, |x, y| Framebuffer::set_pixel(x, y, color));
+  }
+}
+
+/// Draws a line between two points that is `thickness` pixels wide.
+///
+/// Uses `DrawColor::C1` for the line.
+pub fn thick_line(x1: i32, y1: i32, x2: i32, y2: i32, thickness: u32) {
+  let Some(color) = DrawColor::C1.load() else {
+    return;
+  };
+
+  let [a, b, c, d] = thick_line_points(x1, y1, x2, y2, thickness);
+  let points = [a, b, c, d];
+
+  polygon_spans(&points, |y, start_x, end_x| span(y, start_x, end_x, color));
+  edges(&points, |x, y| Framebuffer::set_pixel(x, y, color));
+}
+
+/// Returns the fill and outline colours.
+#[inline]
+fn colors() -> (Option<Palette>, Option<Palette>) {
+  let colors = DrawColors::load();
+  (colors.get(DrawColor::C1), colors.get(DrawColor::C2))
+}
+
+/// Sets the pixels from `start_x` to `end_x` inclusive on row `y`.
+#[inline]
+fn span(y: i32, start_x: i32, end_x: i32, color: Palette) {
+  // Clipped first so that the width of very wide spans fits.
+  let start_x = start_x.max(0);
+  let end_x = end_x.min(Framebuffer::WIDTH - 1);
+
+  if start_x <= end_x {
+    Framebuffer::fill(start_x, y, (end_x - start_x) as u32 + 1, 1, color);
+  }
+}
+
+/// Draws a convex shape described by the inclusive span of each row from
+/// `top` to `bottom`.
+///
+/// A pixel is part of the outline when one of its neighbours is outside of
+/// the shape.
+fn convex(
+  top: i32,
+  bottom: i32,
+  spans: impl Fn(i32) -> Option<(i32, i32)>,
+  fill: Option<Palette>,
+  stroke: Option<Palette>,
+) {
+  for y in top.max(-1)..=bottom.min(Framebuffer::HEIGHT) {
+    let Some((start_x, end_x)) = spans(y) else {
+      continue;
+    };
+
+    let Some(stroke) = stroke else {
+      if let Some(fill) = fill {
+        span(y, start_x, end_x, fill);
+      }
+      continue;
+    };
+
+    // The interior is the part of the row with every neighbour inside.
+    let (mut inner_start, mut inner_end) = (start_x + 1, end_x - 1);
+    for row in [y - 1, y + 1] {
+      match spans(row) {
+        Some((start_x, end_x)) => {
+          inner_start = inner_start.max(start_x + 1);
+          inner_end = inner_end.min(end_x - 1);
+        }
+        None => (inner_start, inner_end) = (end_x + 1, start_x - 1),
+      }
+    }
+
+    if inner_start > inner_end {
+      span(y, start_x, end_x, stroke);
+      continue;
+    }
+
+    span(y, start_x, inner_start - 1, stroke);
+    span(y, inner_end + 1, end_x, stroke);
+    if let Some(fill) = fill {
+      span(y, inner_start, inner_end, fill);
+    }
+  }
+}
+
+/// Returns how far a circle extends either side of its center `dy` rows
+/// away, or [`None`] if it doesn't reach that far.
+#[inline]
+fn circle_width(radius: i32, dy: i64) -> Option<i32> {
+  let radius = radius as i64;
+
+  match dy.abs() <= radius {
+    true => Some(((radius * radius + radius - dy * dy) as u64).isqrt() as i32),
+    false => None,
+  }
+}
+
+/// Returns the inclusive span of row `y` within a circle.
+#[inline]
+pub(crate) fn circle_span(
+  x: i32,
+  y: i32,
+  radius: i32,
+  row: i32,
+) -> Option<(i32, i32)> {
+  let width = circle_width(radius, row as i64 - y as i64)?;
+  Some((x.saturating_sub(width), x.saturating_add(width)))
+}
+
+/// Returns the inclusive span of row `y` within a rounded rectangle.
+pub(crate) fn round_rect_span(
+  x: i32,
+  y: i32,
+  width: u32,
+  height: u32,
+  radius: u32,
+  row: i32,
+) -> Option<(i32, i32)> {
+  let (x, y, row) = (x as i64, y as i64, row as i64);
+  let (width, height) = (width as i64, height as i64);
+  if !(y..y + height).contains(&row) || width <= 0 {
+    return None;
+  }
+
+  let radius = (radius as i64).min((width - 1) / 2).min((height - 1) / 2);
+  let from_top = row - y;
+  let from_bottom = y + height - 1 - row;
+  let dy = radius - from_top.min(from_bottom);
+
+  let inset = match dy > 0 {
+    true => radius - circle_width(radius as i32, dy).unwrap_or(0) as i64,
+    false => 0,
+  };
+
+  let clamp = |x: i64| x.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+  Some((clamp(x + inset), clamp(x + width - 1 - inset)))
+}
+
+/// Calls `f` with each row and inclusive span inside of the polygon, using
+/// the even-odd rule.
+///
+/// Panics if there are more than [`MAX_POINTS`] points.
+pub(crate) fn polygon_spans(
+  points: &[(i32, i32)],
+  mut f: impl FnMut(i32, i32, i32),
+) {
+  assert!(points.len() <= MAX_POINTS, "too many points");
+
+  let Some(top) = points.iter().map(|p| p.1).min() else {
+    return;
+  };
+  let bottom = points.iter().map(|p| p.1).max().unwrap_or(top);
+
+  let mut crossings = [0; MAX_POINTS];
+  for y in top.max(0)..=bottom.min(Framebuffer::HEIGHT - 1) {
+    let mut len = 0;
+
+    for (i, &(x1, y1)) in points.iter().enumerate() {
+      let (x2, y2) = points[(i + 1) % points.len()];
+      let ((x1, y1), (x2, y2)) = match y1 < y2 {
+        true => ((x1, y1), (x2, y2)),
+        false => ((x2, y2), (x1, y1)),
+      };
+
+      // Half-open so that shared vertices are only counted once.
+      if y1 <= y && y < y2 {
+        let (x1, y1, x2, y2, y) =
+          (x1 as i128, y1 as i128, x2 as i128, y2 as i128, y as i128);
+        // Scaled by two to round to the nearest pixel.
+        let num = 2 * (x1 * (y2 - y1) + (y - y1) * (x2 - x1)) + (y2 - y1);
+        crossings[len] = num.div_euclid(2 * (y2 - y1)) as i32;
+        len += 1;
+      }
+    }
+
+    let crossings = &mut crossings[..len];
+    crossings.sort_unstable();
+
+    for pair in crossings.chunks_exact(2) {
+      f(y, pair[0], pair[1]);
+    }
+  }
+}
+
+/// Calls `f` with each point on the edges of the polygon.
+pub(crate) fn edges(points: &[(i32, i32)], mut f: impl FnMut(i32, i32)) {
+  for (i, &(x1, y1)) in points.iter().enumerate() {
+    let (x2, y2) = points[(i + 1) % points.len()];
+    line_points(x1, y1, x2, y2, &mut f);
+  }
+}
+
+/// Calls `f` with each point on the line between two points.
+///
+/// Only the part of the line along the screen's width or height is stepped
+/// through, so lines that reach far off-screen are as quick as any other.
+pub(crate) fn line_points(
+  x1: i32,
+  y1: i32,
+  x2: i32,
+  y2: i32,
+  mut f: impl FnMut(i32, i32),
+) {
+  let (dx, dy) = (x2 as i64 - x1 as i64, y2 as i64 - y1 as i64);
+
+  match dx.abs() >= dy.abs() {
+    true => axis_points(x1, y1, dx, dy, Framebuffer::WIDTH, f),
+    false => axis_points(y1, x1, dy, dx, Framebuffer::HEIGHT, |y, x| f(x, y)),
+  }
+}
+
+/// Calls `f` with each point on a line that moves one pixel at a time along
+/// its major axis, from `major` to `major + major_len`, while moving from
+/// `minor` to `minor + minor_len` along the other.
+///
+/// Only points with the major axis between zero and `size` are visited, and
+/// the minor axis is rounded the same way as Bresenham's algorithm.
+fn axis_points(
+  major: i32,
+  minor: i32,
+  major_len: i64,
+  minor_len: i64,
+  size: i32,
+  mut f: impl FnMut(i32, i32),
+) {
+  let (major, minor) = (major as i64, minor as i64);
+  let (start, end) = match major_len < 0 {
+    true => (major + major_len, major),
+    false => (major, major + major_len),
+  };
+
+  for m in start.max(0)..=end.min(size as i64 - 1) {
+    let offset = match major_len {
+      0 => 0,
+      _ => {
+        // Rounded to the nearest pixel, with halves away from the start.
+        let num =
+          (m - major) as i128 * minor_len as i128 * major_len.signum() as i128;
+        let den = major_len.abs() as i128;
+        num.signum() * ((2 * num.abs() + den) / (2 * den))
+      }
+    };
+
+    f(m as i32, (minor + offset as i64) as i32);
+  }
+}
+
+/// Returns the corners of a line that is `thickness` pixels wide.
+pub(crate) fn thick_line_points(
+  x1: i32,
+  y1: i32,
+  x2: i32,
+  y2: i32,
+  thickness: u32,
+) -> [(i32, i32); 4] {
+  let (dx, dy) = (x2 as i128 - x1 as i128, y2 as i128 - y1 as i128);
+  let spread = thickness.saturating_sub(1) as i128;
+  // The length in 24.8 fixed-point.
+  let len = ((dx * dx + dy * dy) << 16).isqrt().max(1);
+
+  let round = |n: i128| {
+    let offset = (2 * (n << 8) + len).div_euclid(2 * len);
+    offset.clamp(i32::MIN as i128, i32::MAX as i128) as i32
+  };
+  let (ox, oy) = (round(-dy * spread), round(dx * spread));
+  let (ax, ay) = (ox / 2, oy / 2);
+  let (bx, by) = (ox - ax, oy - ay);
+
+  [
+    (x1.saturating_add(ax), y1.saturating_add(ay)),
+    (x2.saturating_add(ax), y2.saturating_add(ay)),
+    (x2.saturating_sub(bx), y2.saturating_sub(by)),
+    (x1.saturating_sub(bx), y1.saturating_sub(by)),
+  ]
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+  use super::*;
+  use crate::mock;
+
+  /// Fills with palette 1 and outlines with palette 3, or fills the edges
+  /// too when `outline` is false.
+  fn set_colors(outline: bool) {
+    let stroke = outline.then_some(Palette::C4);
+    DrawColors::new(Some(Palette::C2), stroke, None, None).store();
+  }
+
+  /// Returns the top-left corner of the screen, with `.` for palette 0, `o`
+  /// for 1, `x` for 2 and `#` for 3.
+  fn drawn(width: i32, height: i32) -> std::vec::Vec<std::string::String> {
+    (0..height)
+      .map(|y| {
+        (0..width)
+          .map(|x| match mock::pixel(x, y) {
+            Some(0) => '.',
+            Some(1) => 'o',
+            Some(2) => 'x',
+            _ => '#',
+          })
+          .collect()
+      })
+      .collect()
+  }
+
+  #[test]
+  fn circle_pixels() {
+    mock::reset();
+    set_colors(true);
+    circle(4, 4, 3);
+
+    assert_eq!(
+      drawn(9, 9),
+      [
+        ".........",
+        "...###...",
+        "..##o##..",
+        ".##ooo##.",
+        ".#ooooo#.",
+        ".##ooo##.",
+        "..##o##..",
+        "...###...",
+        ".........",
+      ]
+    );
+  }
+
+  #[test]
+  fn circle_without_outline() {
+    mock::reset();
+    set_colors(false);
+    circle(4, 4, 3);
+
+    assert_eq!(
+      drawn(9, 9),
+      [
+        ".........",
+        "...ooo...",
+        "..ooooo..",
+        ".ooooooo.",
+        ".ooooooo.",
+        ".ooooooo.",
+        "..ooooo..",
+        "...ooo...",
+        ".........",
+      ]
+    );
+  }
+
+  #[test]
+  fn round_rect_pixels() {
+    mock::reset();
+    set_colors(true);
+    round_rect(0, 0, 10, 7, 2);
+
+    assert_eq!(
+      drawn(11, 8),
+      [
+        ".########..",
+        "##oooooo##.",
+        "#oooooooo#.",
+        "#oooooooo#.",
+        "#oooooooo#.",
+        "##oooooo##.",
+        ".########..",
+        "...........",
+      ]
+    );
+  }
+
+  #[test]
+  fn triangle_pixels() {
+    mock::reset();
+    set_colors(true);
+    triangle((1, 1), (8, 3), (2, 7));
+
+    assert_eq!(
+      drawn(10, 9),
+      [
+        "..........",
+        ".##.......",
+        ".#o####...",
+        ".#ooooo##.",
+        ".#oooo##..",
+        "..#oo#....",
+        "..###.....",
+        "..#.......",
+        "..........",
+      ]
+    );
+  }
+
+  #[test]
+  fn concave_polygon() {
+    mock::reset();
+    set_colors(true);
+    polygon(&[
+      (0, 0),
+      (9, 0),
+      (9, 7),
+      (6, 7),
+      (6, 3),
+      (3, 3),
+      (3, 7),
+      (0, 7),
+    ]);
+
+    assert_eq!(
+      drawn(11, 9),
+      [
+        "##########.",
+        "#oooooooo#.",
+        "#oooooooo#.",
+        "#oo####oo#.",
+        "#oo#..#oo#.",
+        "#oo#..#oo#.",
+        "#oo#..#oo#.",
+        "####..####.",
+        "...........",
+      ]
+    );
+  }
+
+  #[test]
+  fn self_intersecting_polygon() {
+    mock::reset();
+    set_colors(false);
+    polygon(&[(0, 0), (10, 10), (10, 0), (0, 10)]);
+
+    assert_eq!(
+      drawn(12, 12),
+      [
+        "o.........o.",
+        "oo.......oo.",
+        "ooo.....ooo.",
+        "oooo...oooo.",
+        "ooooo.ooooo.",
+        "ooooooooooo.",
+        "ooooo.ooooo.",
+        "oooo...oooo.",
+        "ooo.....ooo.",
+        "oo.......oo.",
+        "o.........o.",
+        "............",
+      ]
+    );
+  }
+
+  #[test]
+  fn even_odd_polygon() {
+    mock::reset();
+    set_colors(true);
+    polygon(&[(8, 0), (13, 16), (0, 6), (16, 6), (3, 16)]);
+
+    // The pentagon in the middle of the star overlaps twice so isn't filled.
+    assert_eq!(
+      drawn(18, 18),
+      [
+        "........#.........",
+        "........#.........",
+        ".......#o#........",
+        ".......#o#........",
+        ".......#o#........",
+        "......#ooo#.......",
+        "#################.",
+        ".#oooo#...#oooo#..",
+        "..##oo#....#o##...",
+        "....##.....##.....",
+        ".....#.....#......",
+        ".....###.###......",
+        "....#ooo#ooo#.....",
+        "....#oo#.#oo#.....",
+        "....###...###.....",
+        "...##.......##....",
+        "...#.........#....",
+        "..................",
+      ]
+    );
+  }
+
+  #[test]
+  fn thick_line_pixels() {
+    mock::reset();
+    set_colors(true);
+    thick_line(1, 1, 10, 5, 3);
+
+    assert_eq!(
+      drawn(13, 8),
+      [
+        "..oo.........",
+        ".ooooo.......",
+        ".ooooooo.....",
+        "...ooooooo...",
+        ".....ooooooo.",
+        ".......ooooo.",
+        ".........oo..",
+        ".............",
+      ]
+    );
+  }
+
+  #[test]
+  fn huge_circle() {
+    mock::reset();
+    DrawColors::new(Some(Palette::C2), Some(Palette::C4), None, None).store();
+    circle(80, 80, 70000);
+
+    assert_eq!(mock::pixel(0, 0), Some(1));
+    assert_eq!(mock::pixel(159, 159), Some(1));
+  }
+
+  #[test]
+  fn huge_polygon() {
+    mock::reset();
+    DrawColors::new(Some(Palette::C2), Some(Palette::C4), None, None).store();
+    polygon(&[(0, 0), (100000, 5), (5, 100000)]);
+
+    assert_eq!(mock::pixel(0, 0), Some(3));
+    assert_eq!(mock::pixel(80, 80), Some(1));
+  }
+
+  #[test]
+  fn far_off_screen_edges() {
+    mock::reset();
+    set_colors(true);
+    polygon(&[(0, 0), (1_000_000_000, 5), (5, 1_000_000_000)]);
+    polygon(&[(i32::MIN, i32::MIN), (i32::MAX, i32::MIN), (0, i32::MAX)]);
+
+    assert_eq!(mock::pixel(80, 80), Some(1));
+  }
+
+  #[test]
+  fn huge_thick_line() {
+    mock::reset();
+    set_colors(true);
+    thick_line(i32::MIN, i32::MIN, i32::MAX, i32::MAX, u32::MAX);
+    assert_eq!(mock::pixel(0, 159), Some(1));
+
+    mock::reset();
+    set_colors(true);
+    thick_line(-1_000_000_000, 80, 1_000_000_000, 80, 1);
+    assert_eq!(
+      drawn(160, 160).iter().position(|row| row.contains('o')),
+      Some(80)
+    );
+    assert!(drawn(160, 160)[80].chars().all(|c| c == 'o'));
+  }
+}
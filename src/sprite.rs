@@ -0,0 +1,458 @@
+//! Sprites and drawing them safely.
+
+use core::ops::{BitAnd, BitOr, BitOrAssign};
+
+use crate::{w4, Rect};
+
+/// The number of bits used for each pixel of a sprite.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Bpp {
+  /// 1 bit per pixel, using `DrawColor::C1` and `DrawColor::C2`.
+  One = 1,
+  /// 2 bits per pixel, using all four draw colours.
+  Two = 2,
+}
+
+/// Changes how a sprite is drawn.
+///
+/// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#blit-spriteptr-x-y-width-height-flags)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BlitFlags(u32);
+
+impl BlitFlags {
+  /// The sprite uses 1 bit per pixel.
+  pub const BPP1: Self = Self(0);
+  /// The sprite uses 2 bits per pixel.
+  pub const BPP2: Self = Self(1 << 0);
+  /// Flips the sprite horizontally.
+  pub const FLIP_X: Self = Self(1 << 1);
+  /// Flips the sprite vertically.
+  pub const FLIP_Y: Self = Self(1 << 2);
+  /// Rotates the sprite anti-clockwise by 90 degrees.
+  pub const ROTATE: Self = Self(1 << 3);
+
+  /// Returns flags with nothing set.
+  #[inline]
+  pub const fn empty() -> Self {
+    Self(0)
+  }
+
+  /// Create flags from the raw flags word, ignoring unknown bits.
+  #[inline]
+  pub const fn from_bits(bits: u32) -> Self {
+    Self(bits & 0b1111)
+  }
+
+  /// Returns the raw flags word.
+  #[inline]
+  pub const fn bits(self) -> u32 {
+    self.0
+  }
+
+  /// Returns these flags with all of `other` set too.
+  ///
+  /// The same as `|`, but usable in constants.
+  #[inline]
+  pub const fn union(self, other: Self) -> Self {
+    Self(self.0 | other.0)
+  }
+
+  /// Whether all of `other` is set in these flags.
+  #[inline]
+  pub const fn contains(self, other: Self) -> bool {
+    self.0 & other.0 == other.0
+  }
+}
+
+impl BitOr for BlitFlags {
+  type Output = Self;
+
+  #[inline]
+  fn bitor(self, rhs: Self) -> Self {
+    Self(self.0 | rhs.0)
+  }
+}
+
+impl BitOrAssign for BlitFlags {
+  #[inline]
+  fn bitor_assign(&mut self, rhs: Self) {
+    self.0 |= rhs.0;
+  }
+}
+
+impl BitAnd for BlitFlags {
+  type Output = Self;
+
+  #[inline]
+  fn bitand(self, rhs: Self) -> Self {
+    Self(self.0 & rhs.0)
+  }
+}
+
+/// Packed pixel data that can be drawn to the screen.
+///
+/// Rows are packed left to right with the first pixel in the most significant
+/// bits of each byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Sprite<'a> {
+  bytes: &'a [u8],
+  width: u32,
+  height: u32,
+  bpp: Bpp,
+}
+
+impl<'a> Sprite<'a> {
+  /// Create a new sprite, or [`None`] if `bytes` is too short to hold
+  /// `width * height` pixels.
+  #[inline]
+  pub const fn new(
+    bytes: &'a [u8],
+    width: u32,
+    height: u32,
+    bpp: Bpp,
+  ) -> Option<Self> {
+    let Some(bits) = (width as u64 * height as u64).checked_mul(bpp as u64)
+    else {
+      return None;
+    };
+
+    if (bytes.len() as u64) < bits.div_ceil(8) {
+      return None;
+    }
+
+    Some(Self {
+      bytes,
+      width,
+      height,
+      bpp,
+    })
+  }
+
+  /// The packed pixel data.
+  #[inline]
+  pub const fn bytes(&self) -> &'a [u8] {
+    self.bytes
+  }
+
+  /// The width, in pixels.
+  #[inline]
+  pub const fn width(&self) -> u32 {
+    self.width
+  }
+
+  /// The height, in pixels.
+  #[inline]
+  pub const fn height(&self) -> u32 {
+    self.height
+  }
+
+  /// The number of bits used for each pixel.
+  #[inline]
+  pub const fn bpp(&self) -> Bpp {
+    self.bpp
+  }
+
+  /// Replaces the bits-per-pixel in `flags` with this sprite's.
+  #[inline]
+  fn flags(&self, flags: BlitFlags) -> i32 {
+    let bpp = match self.bpp {
+      Bpp::One => BlitFlags::BPP1,
+      Bpp::Two => BlitFlags::BPP2,
+    };
+
+    (flags.bits() & !BlitFlags::BPP2.bits() | bpp.bits()) as i32
+  }
+
+  /// Draws the sprite with its top-left corner at `x` and `y`.
+  ///
+  /// The bits-per-pixel in `flags` is ignored in favour of the sprite's own.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#blit-spriteptr-x-y-width-height-flags)
+  #[inline]
+  pub fn draw(&self, x: i32, y: i32, flags: BlitFlags) {
+    unsafe {
+      w4::blit(
+        self.bytes.as_ptr(),
+        x,
+        y,
+        self.width as i32,
+        self.height as i32,
+        self.flags(flags),
+      )
+    }
+  }
+
+  /// Draws the `src` area of the sprite with its top-left corner at `x` and
+  /// `y`.
+  ///
+  /// The bits-per-pixel in `flags` is ignored in favour of the sprite's own.
+  ///
+  /// Panics if `src` is not within the sprite.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#blitsub-spriteptr-x-y-width-height-srcx-srcy-stride-flags)
+  pub fn draw_sub(&self, x: i32, y: i32, src: Rect, flags: BlitFlags) {
+    assert!(
+      src.x >= 0
+        && src.y >= 0
+        && src.x as u64 + src.width as u64 <= self.width as u64
+        && src.y as u64 + src.height as u64 <= self.height as u64,
+      "source area is outside of the sprite",
+    );
+
+    unsafe {
+      w4::blit_sub(
+        self.bytes.as_ptr(),
+        x,
+        y,
+        src.width as i32,
+        src.height as i32,
+        src.x,
+        src.y,
+        self.width as i32,
+        self.flags(flags),
+      )
+    }
+  }
+}
+
+/// A sprite split into equally sized frames.
+///
+/// Frames are numbered left to right, then top to bottom.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SpriteSheet<'a> {
+  sprite: Sprite<'a>,
+  frame_width: u32,
+  frame_height: u32,
+}
+
+impl<'a> SpriteSheet<'a> {
+  /// Create a new sprite sheet, or [`None`] if the frame size is zero or
+  /// bigger than the sprite.
+  ///
+  /// Any pixels left over on the right and bottom edges are ignored.
+  #[inline]
+  pub const fn new(
+    sprite: Sprite<'a>,
+    frame_width: u32,
+    frame_height: u32,
+  ) -> Option<Self> {
+    if frame_width == 0
+      || frame_height == 0
+      || frame_width > sprite.width
+      || frame_height > sprite.height
+    {
+      return None;
+    }
+
+    Some(Self {
+      sprite,
+      frame_width,
+      frame_height,
+    })
+  }
+
+  /// The sprite holding every frame.
+  #[inline]
+  pub const fn sprite(&self) -> Sprite<'a> {
+    self.sprite
+  }
+
+  /// The width of each frame, in pixels.
+  #[inline]
+  pub const fn frame_width(&self) -> u32 {
+    self.frame_width
+  }
+
+  /// The height of each frame, in pixels.
+  #[inline]
+  pub const fn frame_height(&self) -> u32 {
+    self.frame_height
+  }
+
+  /// The number of frames in each row.
+  #[inline]
+  pub const fn columns(&self) -> u32 {
+    self.sprite.width / self.frame_width
+  }
+
+  /// The number of rows of frames.
+  #[inline]
+  pub const fn rows(&self) -> u32 {
+    self.sprite.height / self.frame_height
+  }
+
+  /// The number of frames.
+  #[inline]
+  pub const fn len(&self) -> u32 {
+    self.columns() * self.rows()
+  }
+
+  /// Whether there are no frames, which is never the case.
+  #[inline]
+  pub const fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Returns the area of the sprite a frame covers, or [`None`] if it
+  /// doesn't exist.
+  #[inline]
+  pub const fn frame(&self, index: u32) -> Option<Rect> {
+    if index >= self.len() {
+      return None;
+    }
+
+    Some(Rect::new(
+      ((index % self.columns()) * self.frame_width) as i32,
+      ((index / self.columns()) * self.frame_height) as i32,
+      self.frame_width,
+      self.frame_height,
+    ))
+  }
+
+  /// Draws a frame with its top-left corner at `x` and `y`.
+  ///
+  /// Nothing is drawn if the frame doesn't exist.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#blitsub-spriteptr-x-y-width-height-srcx-srcy-stride-flags)
+  #[inline]
+  pub fn draw(&self, index: u32, x: i32, y: i32, flags: BlitFlags) {
+    if let Some(src) = self.frame(index) {
+      self.sprite.draw_sub(x, y, src, flags);
+    }
+  }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+  use super::*;
+  use crate::mock;
+
+  /// An 8x1 sprite with only its leftmost pixel set.
+  const DOT: Sprite = match Sprite::new(&[0b1000_0000], 8, 1, Bpp::One) {
+    Some(sprite) => sprite,
+    None => panic!(),
+  };
+
+  /// Returns every pixel that isn't palette colour 0.
+  fn drawn() -> std::vec::Vec<(i32, i32)> {
+    (0..160)
+      .flat_map(|y| (0..160).map(move |x| (x, y)))
+      .filter(|&(x, y)| mock::pixel(x, y) != Some(0))
+      .collect()
+  }
+
+  #[test]
+  fn new_checks_length() {
+    assert!(Sprite::new(&[0], 3, 3, Bpp::One).is_none());
+    assert!(Sprite::new(&[0, 0], 3, 3, Bpp::One).is_some());
+    assert!(Sprite::new(&[0], 4, 1, Bpp::Two).is_some());
+    assert!(Sprite::new(&[0], 5, 1, Bpp::Two).is_none());
+    assert!(Sprite::new(&[], 0, 100, Bpp::Two).is_some());
+    assert!(Sprite::new(&[0; 8], u32::MAX, u32::MAX, Bpp::Two).is_none());
+  }
+
+  #[test]
+  fn draw_flags() {
+    mock::reset();
+    mock::poke(0x14, 0x20);
+
+    DOT.draw(0, 0, BlitFlags::FLIP_X);
+    assert_eq!(drawn(), [(7, 0)]);
+
+    mock::reset();
+    mock::poke(0x14, 0x20);
+    DOT.draw(20, 0, BlitFlags::ROTATE);
+    assert_eq!(drawn(), [(20, 7)]);
+  }
+
+  #[test]
+  fn draw_ignores_bpp_flag() {
+    mock::reset();
+    mock::poke(0x14, 0x20);
+
+    DOT.draw(0, 2, BlitFlags::BPP2);
+    assert_eq!(drawn(), [(0, 2)]);
+  }
+
+  #[test]
+  fn draw_sub() {
+    mock::reset();
+    // Two rows of four 2bpp pixels, with the second row counting up.
+    let bytes = [0, 0b00_01_10_11];
+    let sprite = Sprite::new(&bytes, 4, 2, Bpp::Two).unwrap();
+    mock::poke(0x14, 0x20);
+    mock::poke(0x15, 0x43);
+    sprite.draw_sub(10, 10, Rect::new(1, 1, 3, 1), BlitFlags::FLIP_Y);
+
+    assert_eq!(mock::pixel(10, 10), Some(1));
+    assert_eq!(mock::pixel(11, 10), Some(2));
+    assert_eq!(mock::pixel(12, 10), Some(3));
+    assert_eq!(drawn(), [(10, 10), (11, 10), (12, 10)]);
+  }
+
+  #[test]
+  #[should_panic = "source area is outside of the sprite"]
+  fn draw_sub_out_of_bounds() {
+    DOT.draw_sub(0, 0, Rect::new(4, 0, 5, 1), BlitFlags::empty());
+  }
+
+  #[test]
+  #[should_panic = "source area is outside of the sprite"]
+  fn draw_sub_negative() {
+    DOT.draw_sub(0, 0, Rect::new(-1, 0, 1, 1), BlitFlags::empty());
+  }
+
+  fn area(rect: Option<Rect>) -> Option<(i32, i32, u32, u32)> {
+    rect.map(|rect| (rect.x, rect.y, rect.width, rect.height))
+  }
+
+  #[test]
+  fn sheet_frames() {
+    let bytes = [0; 48];
+    let sprite = Sprite::new(&bytes, 24, 16, Bpp::One).unwrap();
+    let sheet = SpriteSheet::new(sprite, 8, 8).unwrap();
+
+    assert_eq!((sheet.columns(), sheet.rows(), sheet.len()), (3, 2, 6));
+    assert_eq!(area(sheet.frame(0)), Some((0, 0, 8, 8)));
+    assert_eq!(area(sheet.frame(2)), Some((16, 0, 8, 8)));
+    assert_eq!(area(sheet.frame(4)), Some((8, 8, 8, 8)));
+    assert_eq!(area(sheet.frame(6)), None);
+  }
+
+  #[test]
+  fn sheet_leftover_pixels() {
+    let bytes = [0; 25];
+    let sprite = Sprite::new(&bytes, 20, 10, Bpp::One).unwrap();
+    let sheet = SpriteSheet::new(sprite, 8, 4).unwrap();
+
+    assert_eq!((sheet.columns(), sheet.rows(), sheet.len()), (2, 2, 4));
+    assert_eq!(area(sheet.frame(3)), Some((8, 4, 8, 4)));
+    assert_eq!(area(sheet.frame(4)), None);
+  }
+
+  #[test]
+  fn sheet_invalid_frame_size() {
+    let sprite = Sprite::new(&[0; 2], 8, 2, Bpp::One).unwrap();
+
+    assert!(SpriteSheet::new(sprite, 0, 1).is_none());
+    assert!(SpriteSheet::new(sprite, 1, 0).is_none());
+    assert!(SpriteSheet::new(sprite, 9, 1).is_none());
+    assert!(SpriteSheet::new(sprite, 8, 3).is_none());
+    assert!(SpriteSheet::new(sprite, 8, 2).is_some());
+  }
+
+  #[test]
+  fn sheet_draw() {
+    mock::reset();
+    mock::poke(0x14, 0x20);
+    // Two 8x1 frames, with the pixel set moving one to the right.
+    let bytes = [0b1000_0000, 0b0100_0000];
+    let sprite = Sprite::new(&bytes, 16, 1, Bpp::One).unwrap();
+    let sheet = SpriteSheet::new(sprite, 8, 1).unwrap();
+
+    sheet.draw(1, 30, 5, BlitFlags::empty());
+    sheet.draw(2, 60, 5, BlitFlags::empty());
+    assert_eq!(drawn(), [(31, 5)]);
+  }
+}
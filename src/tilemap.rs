@@ -0,0 +1,281 @@
+//! Grids of tiles drawn from a [`SpriteSheet`].
+
+use crate::sprite::{BlitFlags, SpriteSheet};
+use crate::Framebuffer;
+
+/// A single cell of a [`Tilemap`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Tile {
+  /// The frame of the tileset drawn, or [`Tile::EMPTY`]'s for nothing.
+  pub index: u16,
+  /// How the frame is drawn.
+  pub flags: BlitFlags,
+}
+
+impl Tile {
+  /// A tile that draws nothing.
+  pub const EMPTY: Self = Self::new(u16::MAX);
+
+  /// Create a new tile drawing a frame of the tileset.
+  #[inline]
+  pub const fn new(index: u16) -> Self {
+    Self {
+      index,
+      flags: BlitFlags::empty(),
+    }
+  }
+
+  /// Returns the tile flipped horizontally.
+  #[inline]
+  pub const fn flip_x(self) -> Self {
+    self.with_flags(BlitFlags::FLIP_X)
+  }
+
+  /// Returns the tile flipped vertically.
+  #[inline]
+  pub const fn flip_y(self) -> Self {
+    self.with_flags(BlitFlags::FLIP_Y)
+  }
+
+  /// Returns the tile rotated anti-clockwise by 90 degrees.
+  #[inline]
+  pub const fn rotate(self) -> Self {
+    self.with_flags(BlitFlags::ROTATE)
+  }
+
+  /// Returns the tile with the flags set too.
+  #[inline]
+  pub const fn with_flags(mut self, flags: BlitFlags) -> Self {
+    self.flags = self.flags.union(flags);
+    self
+  }
+
+  /// Whether the tile draws nothing.
+  #[inline]
+  pub const fn is_empty(self) -> bool {
+    self.index == Self::EMPTY.index
+  }
+}
+
+/// A grid of tiles, stored row by row, drawn from a tileset.
+///
+/// The map's top-left corner sits at the world's origin, and each tile is the
+/// size of a frame of the tileset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Tilemap<'a> {
+  tiles: &'a [Tile],
+  width: u32,
+  tileset: SpriteSheet<'a>,
+}
+
+impl<'a> Tilemap<'a> {
+  /// Create a new tilemap `width` tiles wide, or [`None`] if the tiles don't
+  /// fill a whole number of rows.
+  #[inline]
+  pub const fn new(
+    tiles: &'a [Tile],
+    width: u32,
+    tileset: SpriteSheet<'a>,
+  ) -> Option<Self> {
+    if width == 0 || !tiles.len().is_multiple_of(width as usize) {
+      return None;
+    }
+
+    Some(Self {
+      tiles,
+      width,
+      tileset,
+    })
+  }
+
+  /// The width, in tiles.
+  #[inline]
+  pub const fn width(&self) -> u32 {
+    self.width
+  }
+
+  /// The height, in tiles.
+  #[inline]
+  pub const fn height(&self) -> u32 {
+    (self.tiles.len() / self.width as usize) as u32
+  }
+
+  /// The sprite sheet the tiles are drawn from.
+  #[inline]
+  pub const fn tileset(&self) -> SpriteSheet<'a> {
+    self.tileset
+  }
+
+  /// The width of each tile, in pixels.
+  #[inline]
+  pub const fn tile_width(&self) -> u32 {
+    self.tileset.frame_width()
+  }
+
+  /// The height of each tile, in pixels.
+  #[inline]
+  pub const fn tile_height(&self) -> u32 {
+    self.tileset.frame_height()
+  }
+
+  /// Returns the tile at a column and row, or [`None`] if it is outside of
+  /// the map.
+  #[inline]
+  pub fn tile(&self, column: i32, row: i32) -> Option<Tile> {
+    if !(0..self.width as i32).contains(&column)
+      || !(0..self.height() as i32).contains(&row)
+    {
+      return None;
+    }
+
+    Some(self.tiles[row as usize * self.width as usize + column as usize])
+  }
+
+  /// Returns the tile covering a point in the world, or [`None`] if it is
+  /// outside of the map.
+  ///
+  /// Useful for collision checks.
+  #[inline]
+  pub fn tile_at(&self, x: i32, y: i32) -> Option<Tile> {
+    self.tile(
+      x.div_euclid(self.tile_width() as i32),
+      y.div_euclid(self.tile_height() as i32),
+    )
+  }
+
+  /// Draws the tiles visible on screen, with `camera_x` and `camera_y` being
+  /// the point in the world shown at the top-left of the screen.
+  pub fn draw(&self, camera_x: i32, camera_y: i32) {
+    let (tile_width, tile_height) =
+      (self.tile_width() as i32, self.tile_height() as i32);
+
+    let start_column = camera_x.div_euclid(tile_width).max(0);
+    let start_row = camera_y.div_euclid(tile_height).max(0);
+    let end_column = camera_x
+      .saturating_add(Framebuffer::WIDTH - 1)
+      .div_euclid(tile_width)
+      .min(self.width as i32 - 1);
+    let end_row = camera_y
+      .saturating_add(Framebuffer::HEIGHT - 1)
+      .div_euclid(tile_height)
+      .min(self.height() as i32 - 1);
+
+    for row in start_row..=end_row {
+      for column in start_column..=end_column {
+        let Some(tile) = self.tile(column, row) else {
+          continue;
+        };
+
+        if !tile.is_empty() {
+          self.tileset.draw(
+            tile.index as u32,
+            column * tile_width - camera_x,
+            row * tile_height - camera_y,
+            tile.flags,
+          );
+        }
+      }
+    }
+  }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+  use super::*;
+  use crate::mock;
+  use crate::sprite::{Bpp, Sprite};
+
+  /// Two 8x8 frames, the first with its top-left pixel set and the second
+  /// with the pixel one down and to the right of that.
+  const TILESET: [u8; 16] = {
+    let mut bytes = [0; 16];
+    bytes[0] = 0b1000_0000;
+    bytes[3] = 0b0100_0000;
+    bytes
+  };
+
+  const TILES: [Tile; 6] = [
+    Tile::new(0),
+    Tile::EMPTY,
+    Tile::new(0).flip_x(),
+    Tile::new(0).flip_y(),
+    Tile::new(1),
+    Tile::new(0).rotate(),
+  ];
+
+  fn map() -> Tilemap<'static> {
+    let sprite = Sprite::new(&TILESET, 16, 8, Bpp::One).unwrap();
+    let tileset = SpriteSheet::new(sprite, 8, 8).unwrap();
+    Tilemap::new(&TILES, 3, tileset).unwrap()
+  }
+
+  /// Returns every pixel that isn't palette colour 0.
+  fn drawn() -> std::vec::Vec<(i32, i32)> {
+    (0..160)
+      .flat_map(|y| (0..160).map(move |x| (x, y)))
+      .filter(|&(x, y)| mock::pixel(x, y) != Some(0))
+      .collect()
+  }
+
+  #[test]
+  fn new_checks_rows() {
+    let map = map();
+    assert_eq!((map.width(), map.height()), (3, 2));
+
+    assert!(Tilemap::new(&TILES, 0, map.tileset()).is_none());
+    assert!(Tilemap::new(&TILES, 4, map.tileset()).is_none());
+    assert!(Tilemap::new(&TILES, 6, map.tileset()).is_some());
+  }
+
+  #[test]
+  fn tile_at() {
+    let map = map();
+
+    assert_eq!(map.tile_at(0, 0).map(|tile| tile.index), Some(0));
+    assert_eq!(map.tile_at(23, 15).map(|tile| tile.index), Some(0));
+    assert_eq!(map.tile_at(8, 8).map(|tile| tile.index), Some(1));
+    assert!(map.tile_at(15, 7).is_some_and(Tile::is_empty));
+
+    for (x, y) in [(-1, 0), (0, -1), (24, 0), (0, 16), (i32::MIN, i32::MAX)] {
+      assert!(map.tile_at(x, y).is_none(), "({x}, {y})");
+    }
+  }
+
+  #[test]
+  fn draw_flags() {
+    mock::reset();
+    mock::poke(0x14, 0x20);
+    map().draw(0, 0);
+
+    // The flipped and rotated tiles move their pixel to another corner.
+    assert_eq!(drawn(), [(0, 0), (23, 0), (9, 9), (0, 15), (16, 15)]);
+  }
+
+  #[test]
+  fn draw_camera() {
+    mock::reset();
+    mock::poke(0x14, 0x20);
+    map().draw(5, -3);
+
+    assert_eq!(drawn(), [(18, 3), (4, 12), (11, 18)]);
+  }
+
+  #[test]
+  fn draw_culled() {
+    mock::reset();
+    mock::poke(0x14, 0x20);
+    map().draw(-150, -150);
+
+    // Only the top-left of the map is on screen.
+    assert_eq!(drawn(), [(150, 150), (159, 159)]);
+
+    mock::reset();
+    mock::poke(0x14, 0x20);
+    let map = map();
+    for (x, y) in [(i32::MAX, 0), (0, i32::MAX), (i32::MIN, i32::MIN), (24, 0)]
+    {
+      map.draw(x, y);
+    }
+    assert_eq!(drawn(), []);
+  }
+}
@@ -0,0 +1,341 @@
+//! Typed sounds for [`w4::tone`].
+
+use crate::note::Note;
+use crate::w4;
+
+/// The channel a tone is played on.
+///
+/// [WASM-4 Docs](https://wasm4.org/docs/guides/audio#channels)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Channel {
+  /// The first pulse wave channel.
+  #[default]
+  Pulse1 = 0,
+  /// The second pulse wave channel.
+  Pulse2,
+  /// The triangle wave channel.
+  Triangle,
+  /// The noise channel.
+  Noise,
+}
+
+impl_variants!(
+  /// Every channel, in order.
+  Channel { Pulse1, Pulse2, Triangle, Noise }
+);
+
+/// The duty cycle of the pulse wave channels.
+///
+/// [WASM-4 Docs](https://wasm4.org/docs/guides/audio#duty-cycle)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum DutyCycle {
+  /// 12.5% duty cycle.
+  #[default]
+  Eighth = 0,
+  /// 25% duty cycle.
+  Quarter,
+  /// 50% duty cycle.
+  Half,
+  /// 75% duty cycle.
+  ThreeQuarters,
+}
+
+impl_variants!(
+  /// Every duty cycle, from narrowest to widest.
+  DutyCycle { Eighth, Quarter, Half, ThreeQuarters }
+);
+
+/// Which speakers a tone is played through.
+///
+/// [WASM-4 Docs](https://wasm4.org/docs/guides/audio#pan)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Pan {
+  /// Both speakers.
+  #[default]
+  Center = 0,
+  /// Only the left speaker.
+  Left,
+  /// Only the right speaker.
+  Right,
+}
+
+impl_variants!(
+  /// Every pan, in order.
+  Pan { Center, Left, Right }
+);
+
+/// Cuts off whatever is playing on a channel.
+pub(crate) const SILENCE: Tone = Tone::new(0).sustain(0).volume(0);
+
+/// A sound to be played with [`w4::tone`].
+///
+/// Durations are measured in frames and volumes range from 0 to 100.
+///
+/// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#tone-frequency-duration-volume-flags)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Tone {
+  frequency: u16,
+  end_frequency: u16,
+  attack: u8,
+  decay: u8,
+  sustain: u8,
+  release: u8,
+  volume: u8,
+  peak: u8,
+  pub(crate) channel: Channel,
+  duty_cycle: DutyCycle,
+  pan: Pan,
+  note_mode: bool,
+}
+
+impl Tone {
+  /// Create a new tone at `frequency` Hz.
+  ///
+  /// It sustains for 10 frames at full volume on [`Channel::Pulse1`] with a
+  /// [`DutyCycle::Eighth`] duty cycle through both speakers.
+  #[inline]
+  pub const fn new(frequency: u16) -> Self {
+    Self {
+      frequency,
+      end_frequency: 0,
+      attack: 0,
+      decay: 0,
+      sustain: 10,
+      release: 0,
+      volume: 100,
+      peak: 0,
+      channel: Channel::Pulse1,
+      duty_cycle: DutyCycle::Eighth,
+      pan: Pan::Center,
+      note_mode: false,
+    }
+  }
+
+  /// Create a new tone playing `note`, otherwise the same as [`Tone::new`].
+  #[inline]
+  pub const fn from_note(note: Note) -> Self {
+    Self::new(0).note(note)
+  }
+
+  /// Sets the starting frequency in Hz.
+  #[inline]
+  pub const fn frequency(mut self, frequency: u16) -> Self {
+    self.frequency = frequency;
+    self.note_mode = false;
+    self
+  }
+
+  /// Sets the starting note, which the console tunes exactly rather than
+  /// rounding to a whole frequency.
+  ///
+  /// Slides are then measured in MIDI note numbers rather than Hz, see
+  /// [`Tone::slide_note`].
+  #[inline]
+  pub const fn note(mut self, note: Note) -> Self {
+    self.frequency = note.midi() as u16;
+    self.note_mode = true;
+    self
+  }
+
+  /// Slides towards `note` over the tone's duration.
+  #[inline]
+  pub const fn slide_note(mut self, note: Note) -> Self {
+    self.end_frequency = match self.note_mode {
+      true => note.midi() as u16,
+      false => note.frequency(),
+    };
+    self
+  }
+
+  /// Slides the frequency towards `frequency` Hz over the tone's duration.
+  #[inline]
+  pub const fn slide(mut self, frequency: u16) -> Self {
+    self.end_frequency = frequency;
+    self
+  }
+
+  /// Sets the frames taken to rise from silence to the peak volume.
+  #[inline]
+  pub const fn attack(mut self, frames: u8) -> Self {
+    self.attack = frames;
+    self
+  }
+
+  /// Sets the frames taken to fall from the peak volume to the volume.
+  #[inline]
+  pub const fn decay(mut self, frames: u8) -> Self {
+    self.decay = frames;
+    self
+  }
+
+  /// Sets the frames the volume is held for.
+  #[inline]
+  pub const fn sustain(mut self, frames: u8) -> Self {
+    self.sustain = frames;
+    self
+  }
+
+  /// Sets the frames taken to fall from the volume to silence.
+  #[inline]
+  pub const fn release(mut self, frames: u8) -> Self {
+    self.release = frames;
+    self
+  }
+
+  /// Sets the volume held during the sustain.
+  #[inline]
+  pub const fn volume(mut self, volume: u8) -> Self {
+    self.volume = volume;
+    self
+  }
+
+  /// Sets the volume reached at the end of the attack.
+  ///
+  /// A peak of 0 is treated as 100 by the console.
+  #[inline]
+  pub const fn peak(mut self, volume: u8) -> Self {
+    self.peak = volume;
+    self
+  }
+
+  /// Sets the channel the tone is played on.
+  #[inline]
+  pub const fn channel(mut self, channel: Channel) -> Self {
+    self.channel = channel;
+    self
+  }
+
+  /// Sets the duty cycle, which only affects the pulse channels.
+  #[inline]
+  pub const fn duty_cycle(mut self, duty_cycle: DutyCycle) -> Self {
+    self.duty_cycle = duty_cycle;
+    self
+  }
+
+  /// Sets which speakers the tone is played through.
+  #[inline]
+  pub const fn pan(mut self, pan: Pan) -> Self {
+    self.pan = pan;
+    self
+  }
+
+  /// The number of frames from the start of the attack to the end of the
+  /// release.
+  #[inline]
+  pub const fn duration(self) -> u32 {
+    self.attack as u32
+      + self.decay as u32
+      + self.sustain as u32
+      + self.release as u32
+  }
+
+  /// Packs the tone into the frequency, duration, volume and flags arguments
+  /// of [`w4::tone`].
+  pub const fn encode(self) -> [u32; 4] {
+    let frequency = self.frequency as u32 | (self.end_frequency as u32) << 16;
+    let duration = (self.attack as u32) << 24
+      | (self.decay as u32) << 16
+      | (self.release as u32) << 8
+      | self.sustain as u32;
+    let volume = (self.peak as u32) << 8 | self.volume as u32;
+    let flags = (self.note_mode as u32) << 6
+      | (self.pan as u32) << 4
+      | (self.duty_cycle as u32) << 2
+      | self.channel as u32;
+
+    [frequency, duration, volume, flags]
+  }
+
+  /// Plays the tone.
+  ///
+  /// [WASM-4 Docs](https://wasm4.org/docs/reference/functions#tone-frequency-duration-volume-flags)
+  #[inline]
+  pub fn play(self) {
+    let [frequency, duration, volume, flags] = self.encode();
+    unsafe {
+      w4::tone(
+        frequency as i32,
+        duration as i32,
+        volume as i32,
+        flags as i32,
+      )
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn encode_defaults() {
+    assert_eq!(Tone::new(440).encode(), [440, 10, 100, 0]);
+  }
+
+  #[test]
+  fn encode_frequency_slide() {
+    let [frequency, ..] = Tone::new(262).slide(523).encode();
+    assert_eq!(frequency, 523 << 16 | 262);
+  }
+
+  #[test]
+  fn encode_envelope() {
+    let tone = Tone::new(440)
+      .attack(1)
+      .decay(2)
+      .sustain(3)
+      .release(4)
+      .volume(50)
+      .peak(80);
+    let [_, duration, volume, _] = tone.encode();
+
+    assert_eq!(duration, 0x01_02_04_03);
+    assert_eq!(volume, 80 << 8 | 50);
+  }
+
+  #[test]
+  fn encode_flags() {
+    let tone = Tone::new(440)
+      .channel(Channel::Noise)
+      .duty_cycle(DutyCycle::ThreeQuarters)
+      .pan(Pan::Right);
+    let [.., flags] = tone.encode();
+
+    assert_eq!(flags, 0b10_11_11);
+  }
+
+  #[test]
+  fn variants() {
+    assert_eq!(Channel::ALL.map(|v| v as u8), [0, 1, 2, 3]);
+    assert_eq!(DutyCycle::ALL.map(|v| v as u8), [0, 1, 2, 3]);
+    assert_eq!(Pan::ALL.map(|v| v as u8), [0, 1, 2]);
+
+    for value in 0..=u8::MAX {
+      assert_eq!(
+        Channel::try_from(value).ok(),
+        Channel::ALL.get(value as usize).copied()
+      );
+      assert_eq!(
+        DutyCycle::try_from(value).ok(),
+        DutyCycle::ALL.get(value as usize).copied()
+      );
+      assert_eq!(
+        Pan::try_from(value).ok(),
+        Pan::ALL.get(value as usize).copied()
+      );
+    }
+    assert_eq!(Pan::try_from(3), Err(crate::InvalidValue(3)));
+  }
+
+  #[test]
+  fn encode_note_mode() {
+    let tone = Tone::from_note(Note::A4).slide_note(Note::MIDDLE_C);
+    let [frequency, .., flags] = tone.encode();
+
+    assert_eq!(frequency, 60 << 16 | 69);
+    assert_eq!(flags, 1 << 6);
+  }
+}
@@ -0,0 +1,303 @@
+//! A decoder for zlib streams.
+//!
+//! [RFC 1950](https://www.rfc-editor.org/rfc/rfc1950) and
+//! [RFC 1951](https://www.rfc-editor.org/rfc/rfc1951)
+
+/// The base lengths for length codes 257 to 285.
+const LENGTH_BASE: [u16; 29] = [
+  3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67,
+  83, 99, 115, 131, 163, 195, 227, 258,
+];
+/// The extra bits for length codes 257 to 285.
+const LENGTH_EXTRA: [u8; 29] = [
+  0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5,
+  5, 5, 0,
+];
+/// The base distances for distance codes 0 to 29.
+const DIST_BASE: [u16; 30] = [
+  1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513,
+  769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+/// The extra bits for distance codes 0 to 29.
+const DIST_EXTRA: [u8; 30] = [
+  0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11,
+  11, 12, 12, 13, 13,
+];
+/// The order code length code lengths are stored in.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+  16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Decompresses a zlib stream.
+pub fn inflate(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+  let [cmf, flg, ..] = *data else {
+    return Err("truncated zlib header");
+  };
+  if cmf & 0x0f != 8 || (u16::from(cmf) << 8 | u16::from(flg)) % 31 != 0 {
+    return Err("invalid zlib header");
+  }
+  if flg & 0x20 != 0 {
+    return Err("zlib preset dictionaries are not supported");
+  }
+
+  let mut bits = Bits::new(&data[2..]);
+  let mut out = Vec::new();
+
+  loop {
+    let last = bits.read(1)? == 1;
+
+    match bits.read(2)? {
+      0 => stored(&mut bits, &mut out)?,
+      1 => {
+        let (lit, dist) = fixed();
+        compressed(&mut bits, &mut out, &lit, &dist)?;
+      }
+      2 => {
+        let (lit, dist) = dynamic(&mut bits)?;
+        compressed(&mut bits, &mut out, &lit, &dist)?;
+      }
+      _ => return Err("invalid deflate block type"),
+    }
+
+    if last {
+      return Ok(out);
+    }
+  }
+}
+
+/// Reads bits least significant first.
+struct Bits<'a> {
+  data: &'a [u8],
+  pos: usize,
+}
+
+impl<'a> Bits<'a> {
+  fn new(data: &'a [u8]) -> Self {
+    Self { data, pos: 0 }
+  }
+
+  fn bit(&mut self) -> Result<u32, &'static str> {
+    let byte = self
+      .data
+      .get(self.pos / 8)
+      .ok_or("truncated deflate stream")?;
+    let bit = (byte >> (self.pos % 8)) & 1;
+    self.pos += 1;
+    Ok(u32::from(bit))
+  }
+
+  fn read(&mut self, count: u8) -> Result<u32, &'static str> {
+    let mut value = 0;
+    for i in 0..count {
+      value |= self.bit()? << i;
+    }
+    Ok(value)
+  }
+
+  fn align(&mut self) {
+    self.pos = self.pos.next_multiple_of(8);
+  }
+}
+
+/// A canonical Huffman code.
+struct Huffman {
+  /// The number of codes of each length.
+  counts: [u16; 16],
+  /// The symbols ordered by code.
+  symbols: Vec<u16>,
+}
+
+impl Huffman {
+  fn new(lengths: &[u8]) -> Self {
+    let mut counts = [0; 16];
+    for &len in lengths {
+      counts[len as usize] += 1;
+    }
+    counts[0] = 0;
+
+    let mut offsets = [0; 16];
+    for len in 1..16 {
+      offsets[len] = offsets[len - 1] + counts[len - 1];
+    }
+
+    let mut symbols = vec![0; lengths.len()];
+    for (symbol, &len) in lengths.iter().enumerate() {
+      if len != 0 {
+        symbols[offsets[len as usize] as usize] = symbol as u16;
+        offsets[len as usize] += 1;
+      }
+    }
+
+    Self { counts, symbols }
+  }
+
+  fn decode(&self, bits: &mut Bits) -> Result<u16, &'static str> {
+    let (mut code, mut first, mut index) = (0, 0, 0);
+
+    for len in 1..16 {
+      code |= bits.bit()? as i32;
+      let count = i32::from(self.counts[len]);
+
+      if code - first < count {
+        return Ok(self.symbols[(index + code - first) as usize]);
+      }
+
+      index += count;
+      first = (first + count) << 1;
+      code <<= 1;
+    }
+
+    Err("invalid huffman code")
+  }
+}
+
+fn stored(bits: &mut Bits, out: &mut Vec<u8>) -> Result<(), &'static str> {
+  bits.align();
+  let len = bits.read(16)?;
+  let nlen = bits.read(16)?;
+  if len != !nlen & 0xffff {
+    return Err("invalid stored block length");
+  }
+
+  let start = bits.pos / 8;
+  let bytes = bits
+    .data
+    .get(start..start + len as usize)
+    .ok_or("truncated stored block")?;
+  out.extend_from_slice(bytes);
+  bits.pos += len as usize * 8;
+  Ok(())
+}
+
+fn fixed() -> (Huffman, Huffman) {
+  let mut lengths = [0; 288];
+  lengths[..144].fill(8);
+  lengths[144..256].fill(9);
+  lengths[256..280].fill(7);
+  lengths[280..].fill(8);
+
+  (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic(bits: &mut Bits) -> Result<(Huffman, Huffman), &'static str> {
+  let hlit = bits.read(5)? as usize + 257;
+  let hdist = bits.read(5)? as usize + 1;
+  let hclen = bits.read(4)? as usize + 4;
+
+  let mut code_lengths = [0; 19];
+  for &i in &CODE_LENGTH_ORDER[..hclen] {
+    code_lengths[i] = bits.read(3)? as u8;
+  }
+  let code = Huffman::new(&code_lengths);
+
+  let mut lengths = Vec::with_capacity(hlit + hdist);
+  while lengths.len() < hlit + hdist {
+    let (len, repeat) = match code.decode(bits)? {
+      symbol @ 0..=15 => (symbol as u8, 1),
+      16 => {
+        let prev = *lengths.last().ok_or("repeat with no previous length")?;
+        (prev, 3 + bits.read(2)?)
+      }
+      17 => (0, 3 + bits.read(3)?),
+      18 => (0, 11 + bits.read(7)?),
+      _ => return Err("invalid code length symbol"),
+    };
+    lengths.extend(std::iter::repeat_n(len, repeat as usize));
+  }
+
+  if lengths.len() != hlit + hdist {
+    return Err("too many code lengths");
+  }
+
+  Ok((
+    Huffman::new(&lengths[..hlit]),
+    Huffman::new(&lengths[hlit..]),
+  ))
+}
+
+fn compressed(
+  bits: &mut Bits,
+  out: &mut Vec<u8>,
+  lit: &Huffman,
+  dist: &Huffman,
+) -> Result<(), &'static str> {
+  loop {
+    let symbol = lit.decode(bits)? as usize;
+
+    match symbol {
+      0..=255 => out.push(symbol as u8),
+      256 => return Ok(()),
+      257..=285 => {
+        let i = symbol - 257;
+        let len =
+          LENGTH_BASE[i] as usize + bits.read(LENGTH_EXTRA[i])? as usize;
+
+        let i = dist.decode(bits)? as usize;
+        if i >= DIST_BASE.len() {
+          return Err("invalid distance symbol");
+        }
+        let dist = DIST_BASE[i] as usize + bits.read(DIST_EXTRA[i])? as usize;
+        if dist > out.len() {
+          return Err("distance too far back");
+        }
+
+        let start = out.len() - dist;
+        for i in 0..len {
+          out.push(out[start + i]);
+        }
+      }
+      _ => return Err("invalid literal or length symbol"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn stored_block() {
+    let data = [
+      0x78, 0x01, 0x01, 0x05, 0x00, 0xfa, 0xff, 0x68, 0x65, 0x6c, 0x6c, 0x6f,
+      0x06, 0x2c, 0x02, 0x15,
+    ];
+    assert_eq!(inflate(&data).unwrap(), b"hello");
+  }
+
+  #[test]
+  fn fixed_block() {
+    // Uses a back-reference overlapping its own output.
+    let data = [
+      0x78, 0xda, 0x4b, 0x4c, 0x4a, 0x4e, 0x84, 0x21, 0x00, 0x1d, 0xe0, 0x04,
+      0x99,
+    ];
+    assert_eq!(inflate(&data).unwrap(), b"abcabcabcabc");
+  }
+
+  #[test]
+  fn dynamic_block() {
+    let data = [
+      0x78, 0xda, 0xa5, 0xcb, 0xb1, 0x01, 0xc0, 0x40, 0x0c, 0xc2, 0x40, 0x09,
+      0xf6, 0xdf, 0x39, 0xe6, 0x57, 0x48, 0x43, 0x71, 0x00, 0x20, 0x55, 0x4c,
+      0xb1, 0xa1, 0x40, 0x5c, 0x84, 0x2b, 0x98, 0x68, 0xe9, 0x61, 0xbb, 0xf5,
+      0xb9, 0x79, 0x75, 0x06, 0xff, 0xfe, 0x1f, 0x23, 0x60, 0x00, 0x91,
+    ];
+    let expected: Vec<u8> = (0..128u32)
+      .map(|i| (((i * i * i) >> 4) & 3) as u8)
+      .collect();
+
+    assert_eq!(inflate(&data).unwrap(), expected);
+  }
+
+  #[test]
+  fn invalid_header() {
+    assert!(inflate(&[0x78]).is_err());
+    assert!(inflate(&[0x78, 0x00, 0x03, 0x00]).is_err());
+    assert!(inflate(&[0x79, 0xda, 0x03, 0x00]).is_err());
+  }
+
+  #[test]
+  fn truncated() {
+    assert!(inflate(&[0x78, 0x01, 0x01, 0x05, 0x00, 0xfa, 0xff, 0x68]).is_err());
+  }
+}
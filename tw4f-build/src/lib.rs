@@ -0,0 +1,623 @@
+//! Build script helpers for [tw4f] games.
+//!
+//! Converts PNG images into `tw4f::sprite::Sprite` constants and MIDI files
+//! or tracker modules into `tw4f::music::Song` constants at compile time, so
+//! assets never have to be pasted into source by hand.
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!   let out = std::env::var("OUT_DIR").unwrap();
+//!
+//!   tw4f_build::Sprites::new()
+//!     .add("PLAYER", "assets/player.png")
+//!     .write(std::path::Path::new(&out).join("sprites.rs"))
+//!     .unwrap();
+//!
+//!   tw4f_build::Songs::new()
+//!     .add("THEME", "assets/theme.mid")
+//!     .write(std::path::Path::new(&out).join("songs.rs"))
+//!     .unwrap();
+//! }
+//!
+//! // src/lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/sprites.rs"));
+//!
+//! // Songs use the instruments in scope.
+//! const INSTRUMENTS: &[tw4f::tone::Tone] = &[/* ... */];
+//! include!(concat!(env!("OUT_DIR"), "/songs.rs"));
+//! ```
+//!
+//! [tw4f]: https://github.com/leonskidev/tw4f
+
+#![deny(missing_docs)]
+
+mod inflate;
+mod midi;
+mod png;
+mod tracker;
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::{error, fs, io};
+
+use png::Pixel;
+
+/// The number of bits used for each pixel of a sprite.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bpp {
+  /// 1 bit per pixel, allowing up to 2 colours.
+  One,
+  /// 2 bits per pixel, allowing up to 4 colours.
+  Two,
+}
+
+impl Bpp {
+  fn bits(self) -> usize {
+    match self {
+      Self::One => 1,
+      Self::Two => 2,
+    }
+  }
+
+  fn colors(self) -> usize {
+    1 << self.bits()
+  }
+}
+
+/// An error that occurred while converting an image.
+#[derive(Debug)]
+pub struct Error {
+  path: PathBuf,
+  kind: ErrorKind,
+}
+
+/// What went wrong while converting an image.
+#[derive(Debug)]
+pub enum ErrorKind {
+  /// The file couldn't be read or written.
+  Io(io::Error),
+  /// The image couldn't be decoded.
+  Decode(String),
+  /// The image uses more colours than the bits-per-pixel allows.
+  TooManyColors {
+    /// The number of colours found.
+    found: usize,
+    /// The most colours allowed.
+    max: usize,
+  },
+  /// The music couldn't be read.
+  Parse(String),
+  /// The constant name isn't a valid Rust identifier.
+  InvalidName(String),
+}
+
+impl Error {
+  /// The file the error occurred in.
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  /// What went wrong.
+  pub fn kind(&self) -> &ErrorKind {
+    &self.kind
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}: ", self.path.display())?;
+
+    match &self.kind {
+      ErrorKind::Io(err) => write!(f, "{err}"),
+      ErrorKind::Decode(err) => write!(f, "invalid image: {err}"),
+      ErrorKind::TooManyColors { found, max } => write!(
+        f,
+        "image has {found} colours, but at most {max} are supported",
+      ),
+      ErrorKind::Parse(err) => write!(f, "invalid music: {err}"),
+      ErrorKind::InvalidName(name) => {
+        write!(f, "`{name}` is not a valid constant name")
+      }
+    }
+  }
+}
+
+impl error::Error for Error {
+  fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+    match &self.kind {
+      ErrorKind::Io(err) => Some(err),
+      _ => None,
+    }
+  }
+}
+
+/// A sprite converted from an image.
+#[derive(Clone, Debug)]
+pub struct Sprite {
+  /// The width, in pixels.
+  pub width: u32,
+  /// The height, in pixels.
+  pub height: u32,
+  /// The number of bits used for each pixel.
+  pub bpp: Bpp,
+  /// The packed pixel data.
+  pub bytes: Vec<u8>,
+}
+
+impl Sprite {
+  /// Converts a PNG image, using the fewest bits-per-pixel that fit its
+  /// colours unless `bpp` is given.
+  ///
+  /// Indexed images keep their palette indices. Otherwise fully transparent
+  /// pixels become index 0 and the remaining colours are ordered from
+  /// lightest to darkest, matching the default WASM-4 palette.
+  pub fn from_png(
+    path: impl AsRef<Path>,
+    bpp: Option<Bpp>,
+  ) -> Result<Self, Error> {
+    let path = path.as_ref();
+    let error = |kind| Error {
+      path: path.to_owned(),
+      kind,
+    };
+
+    let data = fs::read(path).map_err(|err| error(ErrorKind::Io(err)))?;
+    let image =
+      png::decode(&data).map_err(|err| error(ErrorKind::Decode(err)))?;
+    let (indices, colors) = index(&image.pixels);
+
+    let bpp = match bpp {
+      Some(bpp) => bpp,
+      None if colors <= 2 => Bpp::One,
+      None => Bpp::Two,
+    };
+    if colors > bpp.colors() {
+      return Err(error(ErrorKind::TooManyColors {
+        found: colors,
+        max: bpp.colors(),
+      }));
+    }
+
+    Ok(Self {
+      width: image.width,
+      height: image.height,
+      bpp,
+      bytes: pack(&indices, bpp),
+    })
+  }
+
+  /// Returns a Rust constant named `name` holding the sprite.
+  pub fn to_const(&self, name: &str) -> String {
+    let bpp = match self.bpp {
+      Bpp::One => "One",
+      Bpp::Two => "Two",
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(out, "pub const {name}: ::tw4f::sprite::Sprite =");
+    let _ = writeln!(out, "  match ::tw4f::sprite::Sprite::new(");
+    let _ = writeln!(out, "    &[");
+    for line in self.bytes.chunks(12) {
+      let bytes: Vec<_> = line.iter().map(|b| format!("0x{b:02x},")).collect();
+      let _ = writeln!(out, "      {}", bytes.join(" "));
+    }
+    let _ = writeln!(out, "    ],");
+    let _ = writeln!(out, "    {},", self.width);
+    let _ = writeln!(out, "    {},", self.height);
+    let _ = writeln!(out, "    ::tw4f::sprite::Bpp::{bpp},");
+    let _ = writeln!(out, "  ) {{");
+    let _ = writeln!(out, "    Some(sprite) => sprite,");
+    let _ = writeln!(out, "    None => panic!(\"sprite data is too short\"),");
+    let _ = writeln!(out, "  }};");
+    out
+  }
+}
+
+/// Collects images to convert into a single Rust source file.
+#[derive(Default)]
+pub struct Sprites {
+  sprites: Vec<(String, PathBuf, Option<Bpp>)>,
+}
+
+impl Sprites {
+  /// Create a new, empty collection.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds an image to be converted into a constant named `name`.
+  pub fn add(
+    &mut self,
+    name: impl Into<String>,
+    path: impl Into<PathBuf>,
+  ) -> &mut Self {
+    self.sprites.push((name.into(), path.into(), None));
+    self
+  }
+
+  /// Adds an image to be converted with a specific bits-per-pixel.
+  pub fn add_with_bpp(
+    &mut self,
+    name: impl Into<String>,
+    path: impl Into<PathBuf>,
+    bpp: Bpp,
+  ) -> &mut Self {
+    self.sprites.push((name.into(), path.into(), Some(bpp)));
+    self
+  }
+
+  /// Converts every image and writes the constants to `out`.
+  ///
+  /// Cargo is told to rerun the build script whenever an image changes.
+  pub fn write(&self, out: impl AsRef<Path>) -> Result<(), Error> {
+    let mut source = String::from("// @generated by tw4f-build\n");
+
+    for (name, path, bpp) in &self.sprites {
+      println!("cargo:rerun-if-changed={}", path.display());
+
+      if !is_ident(name) {
+        return Err(Error {
+          path: path.clone(),
+          kind: ErrorKind::InvalidName(name.clone()),
+        });
+      }
+
+      source.push('\n');
+      source.push_str(&Sprite::from_png(path, *bpp)?.to_const(name));
+    }
+
+    let out = out.as_ref();
+    fs::write(out, source).map_err(|err| Error {
+      path: out.to_owned(),
+      kind: ErrorKind::Io(err),
+    })
+  }
+}
+
+/// The most rows in each pattern of a song converted from a MIDI file.
+const PATTERN_LEN: usize = 64;
+
+/// What happens on a channel when a row is reached.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Cell {
+  /// Whatever is playing carries on.
+  Empty,
+  /// Whatever is playing is cut off.
+  Off,
+  /// Plays a note with one of the song's instruments.
+  Note {
+    /// The MIDI note number, where 60 is middle C.
+    note: u8,
+    /// The index of the instrument.
+    instrument: u8,
+  },
+}
+
+/// A song converted from a MIDI file or tracker module.
+#[derive(Clone, Debug)]
+pub struct Song {
+  /// The rows of each pattern, with a cell for each channel.
+  pub patterns: Vec<Vec<[Cell; 4]>>,
+  /// The indices of the patterns, in the order they're played.
+  pub order: Vec<u8>,
+  /// The number of frames each row is held for.
+  pub speed: u8,
+  /// Everything that couldn't be represented and was dropped.
+  pub warnings: Vec<String>,
+}
+
+impl Song {
+  /// Converts a Standard MIDI File.
+  ///
+  /// Each beat is split into 4 rows and the tempo at the start sets the
+  /// speed. The first three melodic MIDI channels to play take the pulse and
+  /// triangle channels, percussion takes the noise channel, and each uses
+  /// the instrument with the same index as its channel. Velocities, program
+  /// changes and tempo changes are ignored.
+  pub fn from_midi(path: impl AsRef<Path>) -> Result<Self, Error> {
+    let path = path.as_ref();
+    let error = |kind| Error {
+      path: path.to_owned(),
+      kind,
+    };
+
+    let data = fs::read(path).map_err(|err| error(ErrorKind::Io(err)))?;
+    let mut warnings = Vec::new();
+    let (rows, speed) = midi::convert(&data, &mut warnings)
+      .map_err(|err| error(ErrorKind::Parse(err)))?;
+
+    let mut patterns: Vec<Vec<[Cell; 4]>> = Vec::new();
+    let mut order = Vec::new();
+    for rows in rows.chunks(PATTERN_LEN) {
+      let index = match patterns.iter().position(|pattern| pattern == rows) {
+        Some(index) => index,
+        None => {
+          patterns.push(rows.to_vec());
+          patterns.len() - 1
+        }
+      };
+      order.push(u8::try_from(index).map_err(|_| {
+        error(ErrorKind::Parse(
+          "song has over 256 distinct patterns".into(),
+        ))
+      })?);
+    }
+
+    Ok(Self {
+      patterns,
+      order,
+      speed,
+      warnings,
+    })
+  }
+
+  /// Converts a module in a simple text tracker format.
+  ///
+  /// ```text
+  /// # Lines starting with a hash are comments.
+  /// speed 8
+  /// order 0 0 1
+  ///
+  /// pattern 0
+  /// C-4 0 | ...   | C-2 1 | ...
+  /// G-4 0 | ...   | ...   | C-6 2
+  /// ===   | ...   | ===   | ===
+  ///
+  /// pattern 1
+  /// C#5 0 | A-4 0 | ...   | ...
+  /// ```
+  ///
+  /// Each row has a column per channel separated by `|`. A column is either
+  /// empty (blank, `...` or `---`), a note off (`===`), or a note name and
+  /// octave followed by an optional instrument index. The speed defaults to
+  /// 6 and the order to every pattern once.
+  pub fn from_tracker(path: impl AsRef<Path>) -> Result<Self, Error> {
+    let path = path.as_ref();
+    let error = |kind| Error {
+      path: path.to_owned(),
+      kind,
+    };
+
+    let text =
+      fs::read_to_string(path).map_err(|err| error(ErrorKind::Io(err)))?;
+    let mut warnings = Vec::new();
+    let module = tracker::parse(&text, &mut warnings)
+      .map_err(|err| error(ErrorKind::Parse(err)))?;
+
+    Ok(Self {
+      patterns: module.patterns,
+      order: module.order,
+      speed: module.speed,
+      warnings,
+    })
+  }
+
+  /// Returns a Rust constant named `name` holding the song, using the
+  /// `instruments` expression for its instruments.
+  pub fn to_const(&self, name: &str, instruments: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "pub const {name}: ::tw4f::music::Song = {{");
+    let _ = writeln!(out, "  use ::tw4f::music::Cell;");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "  ::tw4f::music::Song::new(");
+    let _ = writeln!(out, "    &[");
+    for pattern in &self.patterns {
+      let _ = writeln!(out, "      &[");
+      for row in pattern {
+        let cells: Vec<_> = row
+          .iter()
+          .map(|cell| match cell {
+            Cell::Empty => "Cell::EMPTY".to_owned(),
+            Cell::Off => "Cell::OFF".to_owned(),
+            Cell::Note { note, instrument } => {
+              format!("Cell::note({note}, {instrument})")
+            }
+          })
+          .collect();
+        let _ = writeln!(out, "        [{}],", cells.join(", "));
+      }
+      let _ = writeln!(out, "      ],");
+    }
+    let _ = writeln!(out, "    ],");
+    let _ = writeln!(out, "    &[");
+    for line in self.order.chunks(16) {
+      let order: Vec<_> =
+        line.iter().map(|index| format!("{index},")).collect();
+      let _ = writeln!(out, "      {}", order.join(" "));
+    }
+    let _ = writeln!(out, "    ],");
+    let _ = writeln!(out, "    {instruments},");
+    let _ = writeln!(out, "    {},", self.speed);
+    let _ = writeln!(out, "  )");
+    let _ = writeln!(out, "}};");
+    out
+  }
+}
+
+/// Collects MIDI files and tracker modules to convert into a single Rust
+/// source file.
+pub struct Songs {
+  songs: Vec<(String, PathBuf)>,
+  instruments: String,
+}
+
+impl Default for Songs {
+  fn default() -> Self {
+    Self {
+      songs: Vec::new(),
+      instruments: "INSTRUMENTS".into(),
+    }
+  }
+}
+
+impl Songs {
+  /// Create a new, empty collection whose songs use an `INSTRUMENTS`
+  /// constant in scope.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sets the expression every song uses for its instruments, which must be
+  /// a `&[tw4f::tone::Tone]`.
+  pub fn instruments(&mut self, instruments: impl Into<String>) -> &mut Self {
+    self.instruments = instruments.into();
+    self
+  }
+
+  /// Adds a song to be converted into a constant named `name`.
+  ///
+  /// Files ending in `.mid` or `.midi` are read as MIDI files and everything
+  /// else as tracker modules.
+  pub fn add(
+    &mut self,
+    name: impl Into<String>,
+    path: impl Into<PathBuf>,
+  ) -> &mut Self {
+    self.songs.push((name.into(), path.into()));
+    self
+  }
+
+  /// Converts every song and writes the constants to `out`.
+  ///
+  /// Cargo is told to rerun the build script whenever a song changes, and
+  /// to warn about anything that couldn't be represented.
+  pub fn write(&self, out: impl AsRef<Path>) -> Result<(), Error> {
+    let mut source = String::from("// @generated by tw4f-build\n");
+
+    for (name, path) in &self.songs {
+      println!("cargo:rerun-if-changed={}", path.display());
+
+      if !is_ident(name) {
+        return Err(Error {
+          path: path.clone(),
+          kind: ErrorKind::InvalidName(name.clone()),
+        });
+      }
+
+      let extension = path.extension().and_then(|ext| ext.to_str());
+      let song = match extension {
+        Some("mid" | "midi") => Song::from_midi(path)?,
+        _ => Song::from_tracker(path)?,
+      };
+      for warning in &song.warnings {
+        println!("cargo:warning={}: {warning}", path.display());
+      }
+
+      source.push('\n');
+      source.push_str(&song.to_const(name, &self.instruments));
+    }
+
+    let out = out.as_ref();
+    fs::write(out, source).map_err(|err| Error {
+      path: out.to_owned(),
+      kind: ErrorKind::Io(err),
+    })
+  }
+}
+
+/// Maps each pixel to a colour index, returning them and how many colours
+/// are used.
+fn index(pixels: &[Pixel]) -> (Vec<u8>, usize) {
+  if let Some(Pixel::Indexed(_)) = pixels.first() {
+    let indices: Vec<u8> = pixels
+      .iter()
+      .map(|pixel| match pixel {
+        Pixel::Indexed(index) => *index,
+        Pixel::Rgba(_) => 0,
+      })
+      .collect();
+    let colors = indices.iter().max().map_or(0, |max| *max as usize + 1);
+    return (indices, colors);
+  }
+
+  let key = |pixel: &Pixel| match *pixel {
+    Pixel::Rgba([.., 0]) | Pixel::Indexed(_) => None,
+    Pixel::Rgba([r, g, b, _]) => {
+      let luma = 299 * r as u32 + 587 * g as u32 + 114 * b as u32;
+      Some((u32::MAX - luma, [r, g, b]))
+    }
+  };
+
+  let colors: Vec<_> = pixels
+    .iter()
+    .map(key)
+    .collect::<BTreeSet<_>>()
+    .into_iter()
+    .collect();
+  let indices = pixels
+    .iter()
+    .map(|pixel| colors.binary_search(&key(pixel)).unwrap_or(0) as u8)
+    .collect();
+
+  (indices, colors.len())
+}
+
+/// Packs colour indices left to right, most significant bits first.
+fn pack(indices: &[u8], bpp: Bpp) -> Vec<u8> {
+  let bits = bpp.bits();
+  let mut bytes = vec![0; (indices.len() * bits).div_ceil(8)];
+
+  for (i, index) in indices.iter().enumerate() {
+    let bit = i * bits;
+    bytes[bit / 8] |=
+      (index & (bpp.colors() as u8 - 1)) << (8 - bits - bit % 8);
+  }
+
+  bytes
+}
+
+fn is_ident(name: &str) -> bool {
+  let mut chars = name.chars();
+  matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+    && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A 5x1 RGB image of five greys from black to white.
+  const FIVE_GREYS: [u8; 81] = [
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d,
+    0x49, 0x48, 0x44, 0x52, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x01,
+    0x08, 0x02, 0x00, 0x00, 0x00, 0x99, 0x9c, 0xf3, 0xa4, 0x00, 0x00, 0x00,
+    0x18, 0x49, 0x44, 0x41, 0x54, 0x78, 0xda, 0x63, 0x60, 0x60, 0x60, 0x70,
+    0x70, 0x70, 0x68, 0x68, 0x68, 0x38, 0x70, 0xe0, 0xc0, 0xff, 0xff, 0xff,
+    0x01, 0x25, 0x8a, 0x07, 0x7e, 0x4b, 0xe0, 0x97, 0x51, 0x00, 0x00, 0x00,
+    0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+  ];
+
+  #[test]
+  fn index_lightest_first() {
+    let image = png::decode(&FIVE_GREYS).unwrap();
+    let (indices, colors) = index(&image.pixels);
+
+    assert_eq!(indices, [4, 3, 2, 1, 0]);
+    assert_eq!(colors, 5);
+  }
+
+  #[test]
+  fn too_many_colors() {
+    let path = std::env::temp_dir()
+      .join(format!("tw4f-build-{}-five-greys.png", std::process::id()));
+    fs::write(&path, FIVE_GREYS).unwrap();
+
+    let result = Sprite::from_png(&path, None);
+    let _ = fs::remove_file(&path);
+
+    assert!(matches!(
+      result.unwrap_err().kind(),
+      ErrorKind::TooManyColors { found: 5, max: 4 }
+    ));
+  }
+
+  #[test]
+  fn pack_2bpp() {
+    assert_eq!(
+      pack(&[0, 1, 2, 3, 3], Bpp::Two),
+      [0b00_01_10_11, 0b11_00_00_00]
+    );
+  }
+}
@@ -0,0 +1,469 @@
+//! A reader for Standard MIDI Files.
+//!
+//! [MIDI 1.0 Specification](https://midi.org/standard-midi-files-specification)
+
+use crate::Cell;
+
+/// The number of rows each beat is split into.
+pub const ROWS_PER_BEAT: u64 = 4;
+
+/// The MIDI channel used for percussion, counting from 0.
+const PERCUSSION: u8 = 9;
+
+/// A note, measured in ticks.
+struct Note {
+  channel: u8,
+  key: u8,
+  start: u64,
+  end: u64,
+}
+
+/// The notes and tempo changes of every track.
+struct Midi {
+  division: u64,
+  tempos: Vec<(u64, u32)>,
+  notes: Vec<Note>,
+}
+
+/// Converts a MIDI file into rows and the frames each row is held for.
+///
+/// Anything that can't be represented is described in `warnings`.
+pub fn convert(
+  data: &[u8],
+  warnings: &mut Vec<String>,
+) -> Result<(Vec<[Cell; 4]>, u8), String> {
+  let midi = decode(data)?;
+  if midi.notes.is_empty() {
+    return Err("file has no notes".into());
+  }
+
+  let mut tempos = midi.tempos.iter().filter(|(tick, _)| *tick == 0);
+  // The default is 120 beats per minute.
+  let tempo = tempos.next_back().map_or(500_000, |(_, tempo)| *tempo);
+  if midi.tempos.iter().any(|(_, other)| *other != tempo) {
+    warnings.push("tempo changes are not supported and were ignored".into());
+  }
+  let speed = (tempo as u64 * 60 + 2_000_000) / 4_000_000;
+  let speed = speed.clamp(1, u8::MAX as u64) as u8;
+
+  // Melodic channels take the pulse and triangle channels in the order they
+  // first play, while percussion always takes the noise channel.
+  let mut columns: Vec<u8> = Vec::new();
+  for note in &midi.notes {
+    if note.channel != PERCUSSION && !columns.contains(&note.channel) {
+      columns.push(note.channel);
+    }
+  }
+  for channel in columns.iter().skip(3) {
+    warnings.push(format!(
+      "MIDI channel {} has no free channel and was dropped",
+      channel + 1,
+    ));
+  }
+  columns.truncate(3);
+
+  let row = |tick: u64| {
+    ((tick * ROWS_PER_BEAT + midi.division / 2) / midi.division) as usize
+  };
+  let end = |note: &Note| row(note.end).max(row(note.start) + 1);
+  let len = midi.notes.iter().map(end).max().unwrap_or(0);
+  let mut rows = vec![[Cell::Empty; 4]; len + 1];
+
+  let channels = (0..3)
+    .map(|column| columns.get(column).copied())
+    .chain([Some(PERCUSSION)]);
+  for (column, channel) in channels.enumerate() {
+    let Some(channel) = channel else {
+      continue;
+    };
+
+    let mut notes: Vec<_> = midi
+      .notes
+      .iter()
+      .filter(|note| note.channel == channel)
+      .collect();
+    // The highest note wins when several start on the same row.
+    notes.sort_by_key(|note| (row(note.start), u8::MAX - note.key));
+
+    let (mut chords, mut cut) = (0, 0);
+    let mut last: Option<(usize, usize)> = None;
+
+    for note in notes {
+      let (start, end) = (row(note.start), end(note));
+
+      if let Some((last_start, last_end)) = last {
+        if start == last_start {
+          chords += 1;
+          continue;
+        }
+        if last_end < start {
+          rows[last_end][column] = Cell::Off;
+        } else if last_end > start {
+          cut += 1;
+        }
+      }
+
+      rows[start][column] = Cell::Note {
+        note: note.key,
+        instrument: column as u8,
+      };
+      last = Some((start, end));
+    }
+
+    if let Some((_, end)) = last {
+      rows[end][column] = Cell::Off;
+    }
+
+    if chords != 0 {
+      warnings.push(format!(
+        "MIDI channel {}: {chords} notes played at the same time as a \
+         higher note were dropped",
+        channel + 1,
+      ));
+    }
+    if cut != 0 {
+      warnings.push(format!(
+        "MIDI channel {}: {cut} notes were cut short by the next note",
+        channel + 1,
+      ));
+    }
+  }
+
+  Ok((rows, speed))
+}
+
+fn decode(data: &[u8]) -> Result<Midi, String> {
+  let mut chunks = Chunks(data);
+
+  let (kind, header) = chunks.next().ok_or("not a MIDI file")??;
+  if kind != *b"MThd" {
+    return Err("not a MIDI file".into());
+  }
+  let [format_hi, format_lo, _, _, division_hi, division_lo, ..] = *header
+  else {
+    return Err("truncated MIDI header".into());
+  };
+
+  let format = u16::from_be_bytes([format_hi, format_lo]);
+  if format > 1 {
+    return Err(format!("MIDI format {format} is not supported"));
+  }
+  let division = u16::from_be_bytes([division_hi, division_lo]);
+  if division & 0x8000 != 0 {
+    return Err("SMPTE time divisions are not supported".into());
+  }
+  if division == 0 {
+    return Err("invalid time division 0".into());
+  }
+
+  let mut midi = Midi {
+    division: division as u64,
+    tempos: Vec::new(),
+    notes: Vec::new(),
+  };
+  for chunk in chunks {
+    let (kind, body) = chunk?;
+    if kind == *b"MTrk" {
+      track(body, &mut midi)?;
+    }
+  }
+
+  midi.notes.sort_by_key(|note| note.start);
+  Ok(midi)
+}
+
+/// Reads every event of a track.
+fn track(mut data: &[u8], midi: &mut Midi) -> Result<(), String> {
+  let mut tick = 0;
+  let mut status = 0;
+  // The start tick of each key held on each channel.
+  let mut held = [[None; 128]; 16];
+
+  while !data.is_empty() {
+    tick += vlq(&mut data)?;
+
+    let byte = *data.first().ok_or("truncated event")?;
+    // Channel events may leave out their status if it's the same as the
+    // previous one.
+    let event = match byte & 0x80 {
+      0 => status,
+      _ => {
+        data = &data[1..];
+        byte
+      }
+    };
+
+    match event {
+      0xff => {
+        let kind = take(&mut data, 1)?[0];
+        let len = vlq(&mut data)? as usize;
+
+        match (kind, take(&mut data, len)?) {
+          (0x2f, _) => break,
+          (0x51, [a, b, c]) => {
+            midi
+              .tempos
+              .push((tick, u32::from_be_bytes([0, *a, *b, *c])));
+          }
+          _ => {}
+        }
+      }
+      0xf0 | 0xf7 => {
+        let len = vlq(&mut data)? as usize;
+        take(&mut data, len)?;
+      }
+      0x80..=0xef => {
+        status = event;
+        let channel = (event & 0x0f) as usize;
+        let len = match event & 0xf0 {
+          0xc0 | 0xd0 => 1,
+          _ => 2,
+        };
+
+        let (key, start) = match (event & 0xf0, take(&mut data, len)?) {
+          (0x90, [key, velocity]) if *velocity != 0 => {
+            let key = *key & 0x7f;
+            (key, held[channel][key as usize].replace(tick))
+          }
+          (0x80 | 0x90, [key, _]) => {
+            let key = *key & 0x7f;
+            (key, held[channel][key as usize].take())
+          }
+          _ => continue,
+        };
+
+        // A note played again before being released ends the first one.
+        if let Some(start) = start {
+          midi.notes.push(Note {
+            channel: channel as u8,
+            key,
+            start,
+            end: tick,
+          });
+        }
+      }
+      _ => return Err("event has no status".into()),
+    }
+  }
+
+  // Notes that are never released end with the track.
+  for (channel, keys) in held.iter().enumerate() {
+    for (key, start) in keys.iter().enumerate() {
+      if let Some(start) = *start {
+        midi.notes.push(Note {
+          channel: channel as u8,
+          key: key as u8,
+          start,
+          end: tick,
+        });
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/// Iterates over the type and body of each chunk.
+struct Chunks<'a>(&'a [u8]);
+
+impl<'a> Iterator for Chunks<'a> {
+  type Item = Result<([u8; 4], &'a [u8]), String>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.0.is_empty() {
+      return None;
+    }
+
+    let chunk = match *self.0 {
+      [a, b, c, d, e, f, g, h, ref rest @ ..] => {
+        let len = u32::from_be_bytes([e, f, g, h]) as usize;
+        rest
+          .get(..len)
+          .map(|body| ([a, b, c, d], body, &rest[len..]))
+      }
+      _ => None,
+    };
+
+    match chunk {
+      Some((kind, body, rest)) => {
+        self.0 = rest;
+        Some(Ok((kind, body)))
+      }
+      None => {
+        self.0 = &[];
+        Some(Err("truncated chunk".into()))
+      }
+    }
+  }
+}
+
+/// Reads a variable-length quantity.
+fn vlq(data: &mut &[u8]) -> Result<u64, String> {
+  let mut value = 0;
+
+  for _ in 0..4 {
+    let byte = take(data, 1)?[0];
+    value = value << 7 | (byte & 0x7f) as u64;
+    if byte & 0x80 == 0 {
+      return Ok(value);
+    }
+  }
+
+  Err("invalid variable-length quantity".into())
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8], String> {
+  if data.len() < len {
+    return Err("truncated event".into());
+  }
+
+  let (head, tail) = data.split_at(len);
+  *data = tail;
+  Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Builds a format 1 file with 96 ticks per beat, so 24 ticks per row.
+  fn smf(tracks: &[&[u8]]) -> Vec<u8> {
+    let mut data = b"MThd".to_vec();
+    data.extend_from_slice(&6u32.to_be_bytes());
+    data.extend_from_slice(&1u16.to_be_bytes());
+    data.extend_from_slice(&(tracks.len() as u16).to_be_bytes());
+    data.extend_from_slice(&96u16.to_be_bytes());
+
+    for track in tracks {
+      data.extend_from_slice(b"MTrk");
+      data.extend_from_slice(&(track.len() as u32).to_be_bytes());
+      data.extend_from_slice(track);
+    }
+    data
+  }
+
+  fn note(note: u8, instrument: u8) -> Cell {
+    Cell::Note { note, instrument }
+  }
+
+  #[test]
+  fn vlq_lengths() {
+    assert_eq!(vlq(&mut &[0x00][..]), Ok(0));
+    assert_eq!(vlq(&mut &[0x7f][..]), Ok(0x7f));
+    assert_eq!(vlq(&mut &[0x81, 0x00][..]), Ok(0x80));
+    assert_eq!(vlq(&mut &[0xff, 0xff, 0xff, 0x7f][..]), Ok(0x0fff_ffff));
+
+    let mut data = &[0x81, 0x80, 0x00, 0x42][..];
+    assert_eq!(vlq(&mut data), Ok(0x4000));
+    assert_eq!(data, [0x42]);
+  }
+
+  #[test]
+  fn vlq_invalid() {
+    assert!(vlq(&mut &[0x80, 0x80, 0x80, 0x80, 0x00][..]).is_err());
+    assert!(vlq(&mut &[0x80][..]).is_err());
+  }
+
+  #[test]
+  fn running_status_and_zero_velocity() {
+    let data = smf(&[&[
+      0x00, 0x90, 60, 100, // C4 on
+      0x18, 60, 0, // C4 off, as a note on with no velocity
+      0x00, 62, 100, // D4 on
+      0x18, 0x80, 62, 0, // D4 off
+      0x00, 0xff, 0x2f, 0x00,
+    ]]);
+    let mut warnings = Vec::new();
+    let (rows, speed) = convert(&data, &mut warnings).unwrap();
+
+    let column: Vec<_> = rows.iter().map(|row| row[0]).collect();
+    assert_eq!(column, [note(60, 0), note(62, 0), Cell::Off]);
+    assert!(rows.iter().all(|row| row[1..] == [Cell::Empty; 3]));
+    assert_eq!(speed, 8);
+    assert!(warnings.is_empty());
+  }
+
+  #[test]
+  fn chord_keeps_highest() {
+    let data = smf(&[&[
+      0x00, 0x90, 60, 100, // C4 on
+      0x00, 67, 100, // G4 on
+      0x00, 64, 100, // E4 on
+      0x30, 60, 0, // C4 off two rows later
+      0x00, 67, 0, // G4 off
+      0x00, 64, 0, // E4 off
+      0x00, 0xff, 0x2f, 0x00,
+    ]]);
+    let mut warnings = Vec::new();
+    let (rows, _) = convert(&data, &mut warnings).unwrap();
+
+    assert_eq!(rows[0][0], note(67, 0));
+    assert_eq!(rows[2][0], Cell::Off);
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("2 notes"), "{warnings:?}");
+  }
+
+  #[test]
+  fn channels_to_columns() {
+    let data = smf(&[&[
+      0x00, 0x93, 48, 100, // channel 4 plays first
+      0x00, 0x99, 36, 100, // percussion
+      0x00, 0x90, 60, 100, // channel 1
+      0x18, 0x83, 48, 0, // each off a row later
+      0x00, 0x89, 36, 0, // percussion off
+      0x00, 0x80, 60, 0, // channel 1 off
+      0x00, 0xff, 0x2f, 0x00,
+    ]]);
+    let mut warnings = Vec::new();
+    let (rows, _) = convert(&data, &mut warnings).unwrap();
+
+    assert_eq!(
+      rows[0],
+      [note(48, 0), note(60, 1), Cell::Empty, note(36, 3)]
+    );
+    assert_eq!(rows[1], [Cell::Off, Cell::Off, Cell::Empty, Cell::Off]);
+  }
+
+  #[test]
+  fn tempo_to_speed() {
+    let tempo = |tempo: u32| {
+      let [_, a, b, c] = tempo.to_be_bytes();
+      smf(&[
+        &[0x00, 0xff, 0x51, 0x03, a, b, c, 0x00, 0xff, 0x2f, 0x00],
+        &[0x00, 0x90, 60, 100, 0x18, 60, 0, 0x00, 0xff, 0x2f, 0x00],
+      ])
+    };
+    let speed = |data: Vec<u8>| convert(&data, &mut Vec::new()).unwrap().1;
+
+    // 120 beats per minute is 30 frames per beat.
+    assert_eq!(speed(tempo(500_000)), 8);
+    assert_eq!(speed(tempo(250_000)), 4);
+    assert_eq!(speed(tempo(1_000_000)), 15);
+    assert_eq!(speed(tempo(1)), 1);
+  }
+
+  #[test]
+  fn tempo_change_warns() {
+    let data = smf(&[&[
+      0x00, 0xff, 0x51, 0x03, 0x07, 0xa1, 0x20, // 120 beats per minute
+      0x00, 0x90, 60, 100, // C4 on
+      0x18, 0xff, 0x51, 0x03, 0x03, 0xd0, 0x90, // 240 beats per minute
+      0x00, 0x80, 60, 0, // C4 off
+      0x00, 0xff, 0x2f, 0x00,
+    ]]);
+    let mut warnings = Vec::new();
+    let (_, speed) = convert(&data, &mut warnings).unwrap();
+
+    assert_eq!(speed, 8);
+    assert_eq!(warnings.len(), 1);
+  }
+
+  #[test]
+  fn invalid_files() {
+    assert!(convert(b"RIFF", &mut Vec::new()).is_err());
+    assert!(
+      convert(&smf(&[&[0x00, 0xff, 0x2f, 0x00]]), &mut Vec::new()).is_err()
+    );
+    assert!(convert(&smf(&[&[0x00, 60, 100]]), &mut Vec::new()).is_err());
+  }
+}
@@ -0,0 +1,270 @@
+//! A decoder for non-interlaced PNG images.
+//!
+//! [PNG Specification](https://www.w3.org/TR/png/)
+
+use crate::inflate::inflate;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// A single pixel of a decoded image.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Pixel {
+  /// An index into the image's own palette.
+  Indexed(u8),
+  /// A colour with an alpha channel.
+  Rgba([u8; 4]),
+}
+
+/// A decoded image.
+pub struct Image {
+  pub width: u32,
+  pub height: u32,
+  pub pixels: Vec<Pixel>,
+}
+
+/// Decodes a PNG image.
+pub fn decode(data: &[u8]) -> Result<Image, String> {
+  let mut data = data.strip_prefix(&SIGNATURE).ok_or("not a PNG image")?;
+
+  let mut header = None;
+  let mut idat = Vec::new();
+
+  while let [a, b, c, d, rest @ ..] = data {
+    let len = u32::from_be_bytes([*a, *b, *c, *d]) as usize;
+    // The chunk type, data and CRC.
+    let chunk = rest.get(..len + 8).ok_or("truncated chunk")?;
+    let (kind, body) = (&chunk[..4], &chunk[4..4 + len]);
+    data = &rest[len + 8..];
+
+    match kind {
+      b"IHDR" => header = Some(Header::parse(body)?),
+      b"IDAT" => idat.extend_from_slice(body),
+      b"IEND" => break,
+      _ if kind[0].is_ascii_uppercase() && kind != b"PLTE" => {
+        return Err(format!(
+          "unsupported critical chunk `{}`",
+          String::from_utf8_lossy(kind),
+        ));
+      }
+      _ => {}
+    }
+  }
+
+  let header = header.ok_or("missing IHDR chunk")?;
+  let raw = inflate(&idat)?;
+  header.pixels(&raw)
+}
+
+struct Header {
+  width: u32,
+  height: u32,
+  depth: u8,
+  color_type: u8,
+}
+
+impl Header {
+  fn parse(body: &[u8]) -> Result<Self, String> {
+    let body: &[u8; 13] = body.try_into().map_err(|_| "invalid IHDR chunk")?;
+    let width = u32::from_be_bytes([body[0], body[1], body[2], body[3]]);
+    let height = u32::from_be_bytes([body[4], body[5], body[6], body[7]]);
+    let [depth, color_type, compression, filter, interlace] =
+      [body[8], body[9], body[10], body[11], body[12]];
+
+    let depth_ok = match color_type {
+      0 => matches!(depth, 1 | 2 | 4 | 8 | 16),
+      3 => matches!(depth, 1 | 2 | 4 | 8),
+      2 | 4 | 6 => matches!(depth, 8 | 16),
+      _ => return Err(format!("invalid colour type {color_type}")),
+    };
+    if !depth_ok {
+      return Err(format!(
+        "invalid bit depth {depth} for colour type {color_type}"
+      ));
+    }
+    if compression != 0 || filter != 0 {
+      return Err("unsupported compression or filter method".into());
+    }
+    if interlace != 0 {
+      return Err("interlaced images are not supported".into());
+    }
+
+    Ok(Self {
+      width,
+      height,
+      depth,
+      color_type,
+    })
+  }
+
+  fn channels(&self) -> usize {
+    match self.color_type {
+      2 => 3,
+      4 => 2,
+      6 => 4,
+      _ => 1,
+    }
+  }
+
+  fn pixels(&self, raw: &[u8]) -> Result<Image, String> {
+    let bits = self.channels() * self.depth as usize;
+    let stride = (self.width as usize * bits).div_ceil(8);
+    // The distance to the same byte of the previous pixel.
+    let step = bits.div_ceil(8);
+
+    let mut prev = vec![0; stride];
+    let mut row = vec![0; stride];
+    let mut pixels =
+      Vec::with_capacity(self.width as usize * self.height as usize);
+    let mut lines = raw.chunks(stride + 1);
+
+    for _ in 0..self.height {
+      let line = lines
+        .next()
+        .filter(|line| line.len() == stride + 1)
+        .ok_or("truncated image data")?;
+      unfilter(line[0], &line[1..], &prev, &mut row, step)?;
+
+      for x in 0..self.width as usize {
+        pixels.push(self.pixel(&row, x));
+      }
+      std::mem::swap(&mut prev, &mut row);
+    }
+
+    Ok(Image {
+      width: self.width,
+      height: self.height,
+      pixels,
+    })
+  }
+
+  fn pixel(&self, row: &[u8], x: usize) -> Pixel {
+    let sample = |i: usize| -> u8 {
+      match self.depth {
+        16 => row[i * 2],
+        8 => row[i],
+        depth => {
+          let bit = i * depth as usize;
+          let value = row[bit / 8] >> (8 - depth as usize - bit % 8);
+          let value = value & ((1 << depth) - 1);
+          // Scales grey up to 8 bits while leaving indices alone.
+          match self.color_type {
+            3 => value,
+            _ => value * (255 / ((1 << depth) - 1)),
+          }
+        }
+      }
+    };
+
+    let i = x * self.channels();
+    match self.color_type {
+      0 => Pixel::Rgba([sample(i), sample(i), sample(i), 255]),
+      2 => Pixel::Rgba([sample(i), sample(i + 1), sample(i + 2), 255]),
+      3 => Pixel::Indexed(sample(i)),
+      4 => Pixel::Rgba([sample(i), sample(i), sample(i), sample(i + 1)]),
+      _ => {
+        Pixel::Rgba([sample(i), sample(i + 1), sample(i + 2), sample(i + 3)])
+      }
+    }
+  }
+}
+
+fn unfilter(
+  filter: u8,
+  line: &[u8],
+  prev: &[u8],
+  row: &mut [u8],
+  step: usize,
+) -> Result<(), String> {
+  for i in 0..line.len() {
+    let a = if i >= step { row[i - step] } else { 0 };
+    let b = prev[i];
+    let c = if i >= step { prev[i - step] } else { 0 };
+
+    row[i] = line[i].wrapping_add(match filter {
+      0 => 0,
+      1 => a,
+      2 => b,
+      3 => ((a as u16 + b as u16) / 2) as u8,
+      4 => paeth(a, b, c),
+      _ => return Err(format!("invalid filter type {filter}")),
+    });
+  }
+
+  Ok(())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+  let p = a as i16 + b as i16 - c as i16;
+  let (pa, pb, pc) = (
+    (p - a as i16).abs(),
+    (p - b as i16).abs(),
+    (p - c as i16).abs(),
+  );
+
+  if pa <= pb && pa <= pc {
+    a
+  } else if pb <= pc {
+    b
+  } else {
+    c
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rgba() {
+    // 2x3 with sub, up and Paeth filtered rows.
+    let data = [
+      0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d,
+      0x49, 0x48, 0x44, 0x52, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x03,
+      0x08, 0x06, 0x00, 0x00, 0x00, 0xb9, 0xea, 0xde, 0x81, 0x00, 0x00, 0x00,
+      0x21, 0x49, 0x44, 0x41, 0x54, 0x78, 0xda, 0x63, 0xfc, 0xcf, 0xc0, 0xf0,
+      0x9f, 0xf1, 0x3f, 0x43, 0x23, 0x13, 0x23, 0xc3, 0x7f, 0x10, 0x6c, 0x60,
+      0xe1, 0x12, 0x91, 0xd7, 0x34, 0xb6, 0xd5, 0x08, 0x00, 0x00, 0x80, 0x51,
+      0x08, 0x53, 0x3a, 0x70, 0x26, 0xe6, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45,
+      0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+    ];
+    let image = decode(&data).unwrap();
+
+    assert_eq!((image.width, image.height), (2, 3));
+    assert!(
+      image.pixels
+        == [
+          Pixel::Rgba([255, 0, 0, 255]),
+          Pixel::Rgba([0, 255, 0, 128]),
+          Pixel::Rgba([0, 0, 255, 255]),
+          Pixel::Rgba([255, 255, 255, 0]),
+          Pixel::Rgba([10, 20, 30, 40]),
+          Pixel::Rgba([50, 60, 70, 80]),
+        ]
+    );
+  }
+
+  #[test]
+  fn indexed_2bit() {
+    // 3x2, so each row is padded out to a whole byte.
+    let data = [
+      0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d,
+      0x49, 0x48, 0x44, 0x52, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x02,
+      0x02, 0x03, 0x00, 0x00, 0x00, 0xe0, 0x1a, 0x8e, 0x89, 0x00, 0x00, 0x00,
+      0x0c, 0x50, 0x4c, 0x54, 0x45, 0x00, 0x00, 0x00, 0x55, 0x55, 0x55, 0xaa,
+      0xaa, 0xaa, 0xff, 0xff, 0xff, 0xc1, 0x7f, 0x62, 0xd1, 0x00, 0x00, 0x00,
+      0x0c, 0x49, 0x44, 0x41, 0x54, 0x78, 0xda, 0x63, 0x90, 0x60, 0x78, 0x02,
+      0x00, 0x01, 0x30, 0x00, 0xfd, 0x68, 0x30, 0xcf, 0xdf, 0x00, 0x00, 0x00,
+      0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+    ];
+    let image = decode(&data).unwrap();
+
+    assert_eq!((image.width, image.height), (3, 2));
+    assert!(image.pixels == [0, 1, 2, 3, 2, 1].map(Pixel::Indexed));
+  }
+
+  #[test]
+  fn not_png() {
+    assert!(decode(b"GIF89a").is_err());
+    assert!(decode(&SIGNATURE).is_err());
+  }
+}
@@ -0,0 +1,273 @@
+//! A reader for a simple text tracker format.
+//!
+//! ```text
+//! # Lines starting with a hash are comments.
+//! speed 8
+//! order 0 0 1
+//!
+//! pattern 0
+//! C-4 0 | ...   | C-2 1 | ...
+//! E-4 0 | ...   | ...   | ...
+//! G-4 0 | ...   | G-2 1 | C-6 2
+//! ===   | ...   | ===   | ===
+//!
+//! pattern 1
+//! C#5 0 | A-4 0 | ...   | ...
+//! ```
+//!
+//! Each row has a column per channel separated by `|`. A column is either
+//! empty (blank, `...` or `---`), a note off (`===`), or a note name and
+//! octave followed by an optional instrument index.
+
+use crate::Cell;
+
+/// Patterns in the order they were numbered, the order they're played in,
+/// and the frames each row is held for.
+pub struct Module {
+  pub patterns: Vec<Vec<[Cell; 4]>>,
+  pub order: Vec<u8>,
+  pub speed: u8,
+}
+
+/// Reads a module.
+///
+/// Anything that can't be represented is described in `warnings`.
+pub fn parse(text: &str, warnings: &mut Vec<String>) -> Result<Module, String> {
+  let mut speed = 6;
+  let mut order = None;
+  let mut patterns: Vec<(u8, Vec<[Cell; 4]>)> = Vec::new();
+
+  for (number, line) in text.lines().enumerate() {
+    let line = match line.trim() {
+      line if line.starts_with('#') => "",
+      line => line,
+    };
+    let error = |message: String| format!("line {}: {message}", number + 1);
+
+    let mut words = line.split_whitespace();
+    match words.next() {
+      None => {}
+      Some("speed") => {
+        speed = match words.next().map(str::parse) {
+          Some(Ok(speed @ 1..)) => speed,
+          _ => return Err(error("expected a speed from 1 to 255".into())),
+        };
+      }
+      Some("order") => {
+        order = Some(
+          words
+            .map(str::parse)
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| error("expected pattern numbers".into()))?,
+        );
+      }
+      Some("pattern") => {
+        let id = match words.next().map(str::parse) {
+          Some(Ok(id)) => id,
+          _ => return Err(error("expected a pattern number".into())),
+        };
+        if patterns.iter().any(|(other, _)| *other == id) {
+          return Err(error(format!("pattern {id} is defined twice")));
+        }
+        patterns.push((id, Vec::new()));
+      }
+      Some(_) => {
+        let (_, rows) = patterns
+          .last_mut()
+          .ok_or_else(|| error("row is outside of a pattern".into()))?;
+
+        let mut row = [Cell::Empty; 4];
+        for (column, cell) in line.split('|').enumerate() {
+          match row.get_mut(column) {
+            Some(slot) => *slot = parse_cell(cell.trim(), warnings, &error)?,
+            None if cell.trim().is_empty() => {}
+            None => {
+              warnings.push(error(format!(
+                "column {} has no channel and was dropped",
+                column + 1,
+              )));
+            }
+          }
+        }
+        rows.push(row);
+      }
+    }
+  }
+
+  patterns.sort_by_key(|(id, _)| *id);
+  if let Some((id, _)) = patterns.iter().find(|(_, rows)| rows.is_empty()) {
+    return Err(format!("pattern {id} has no rows"));
+  }
+
+  let order = match order {
+    Some(order) => order
+      .iter()
+      .map(
+        |id| match patterns.iter().position(|(other, _)| other == id) {
+          Some(index) => Ok(index as u8),
+          None => Err(format!("order uses undefined pattern {id}")),
+        },
+      )
+      .collect::<Result<Vec<_>, _>>()?,
+    None => (0..patterns.len() as u8).collect(),
+  };
+  if order.is_empty() {
+    return Err("module has nothing to play".into());
+  }
+
+  Ok(Module {
+    patterns: patterns.into_iter().map(|(_, rows)| rows).collect(),
+    order,
+    speed,
+  })
+}
+
+fn parse_cell(
+  cell: &str,
+  warnings: &mut Vec<String>,
+  error: &impl Fn(String) -> String,
+) -> Result<Cell, String> {
+  let mut words = cell.split_whitespace();
+  let (note, instrument) = match (words.next(), words.next(), words.next()) {
+    (None | Some("..." | "---"), None, _) => return Ok(Cell::Empty),
+    (Some("==="), None, _) => return Ok(Cell::Off),
+    (Some(note), instrument, None) => (note, instrument),
+    _ => return Err(error(format!("invalid cell `{cell}`"))),
+  };
+
+  let instrument = match instrument.map(str::parse) {
+    None => 0,
+    Some(Ok(instrument)) => instrument,
+    Some(Err(_)) => {
+      return Err(error(format!("invalid instrument in `{cell}`")))
+    }
+  };
+
+  let key = match note.as_bytes() {
+    [name, accidental, octave @ b'0'..=b'9'] => {
+      let semitone = match name.to_ascii_uppercase() {
+        b'C' => 0,
+        b'D' => 2,
+        b'E' => 4,
+        b'F' => 5,
+        b'G' => 7,
+        b'A' => 9,
+        b'B' => 11,
+        _ => return Err(error(format!("invalid note `{note}`"))),
+      };
+      let accidental = match accidental {
+        b'-' => 0,
+        b'#' => 1,
+        b'b' => -1,
+        _ => return Err(error(format!("invalid note `{note}`"))),
+      };
+      (*octave - b'0' + 1) as i32 * 12 + semitone + accidental
+    }
+    _ => return Err(error(format!("invalid note `{note}`"))),
+  };
+
+  match u8::try_from(key) {
+    Ok(note @ ..=127) => Ok(Cell::Note { note, instrument }),
+    _ => {
+      warnings
+        .push(error(format!("note `{note}` is too high and was dropped")));
+      Ok(Cell::Empty)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn note(note: u8, instrument: u8) -> Cell {
+    Cell::Note { note, instrument }
+  }
+
+  #[test]
+  fn doc_example() {
+    let text = "\
+# Lines starting with a hash are comments.
+speed 8
+order 0 0 1
+
+pattern 0
+C-4 0 | ...   | C-2 1 | ...
+G-4 0 | ...   | ...   | C-6 2
+===   | ...   | ===   | ===
+
+pattern 1
+C#5 0 | A-4 0 | ...   | ...
+";
+    let mut warnings = Vec::new();
+    let module = parse(text, &mut warnings).unwrap();
+
+    assert_eq!(module.speed, 8);
+    assert_eq!(module.order, [0, 0, 1]);
+    assert_eq!(
+      module.patterns,
+      [
+        vec![
+          [note(60, 0), Cell::Empty, note(36, 1), Cell::Empty],
+          [note(67, 0), Cell::Empty, Cell::Empty, note(84, 2)],
+          [Cell::Off, Cell::Empty, Cell::Off, Cell::Off],
+        ],
+        vec![[note(73, 0), note(69, 0), Cell::Empty, Cell::Empty]],
+      ]
+    );
+    assert!(warnings.is_empty());
+  }
+
+  #[test]
+  fn defaults() {
+    let text = "pattern 3\nBb3\npattern 1\n--- | d-1 2\n";
+    let module = parse(text, &mut Vec::new()).unwrap();
+
+    assert_eq!(module.speed, 6);
+    assert_eq!(module.order, [0, 1]);
+    assert_eq!(
+      module.patterns,
+      [
+        vec![[Cell::Empty, note(26, 2), Cell::Empty, Cell::Empty]],
+        vec![[note(58, 0), Cell::Empty, Cell::Empty, Cell::Empty]],
+      ]
+    );
+  }
+
+  #[test]
+  fn warnings() {
+    let text = "pattern 0\nG#9 | ... | ... | ... | C-4\n";
+    let mut warnings = Vec::new();
+    let module = parse(text, &mut warnings).unwrap();
+
+    assert_eq!(module.patterns, [vec![[Cell::Empty; 4]]]);
+    assert_eq!(warnings.len(), 2);
+    assert!(
+      warnings[0].starts_with("line 2: note `G#9`"),
+      "{warnings:?}"
+    );
+  }
+
+  #[test]
+  fn errors() {
+    let error = |text| parse(text, &mut Vec::new()).err().unwrap();
+
+    assert_eq!(error("C-4"), "line 1: row is outside of a pattern");
+    assert_eq!(error("speed 0"), "line 1: expected a speed from 1 to 255");
+    assert_eq!(error("pattern 0\nH-4"), "line 2: invalid note `H-4`");
+    assert_eq!(
+      error("pattern 0\nC-4 x"),
+      "line 2: invalid instrument in `C-4 x`"
+    );
+    assert_eq!(
+      error("order 1\npattern 0\nC-4"),
+      "order uses undefined pattern 1"
+    );
+    assert_eq!(
+      error("pattern 0\npattern 0"),
+      "line 2: pattern 0 is defined twice"
+    );
+    assert_eq!(error("pattern 0\n"), "pattern 0 has no rows");
+    assert_eq!(error(""), "module has nothing to play");
+  }
+}